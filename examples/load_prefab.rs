@@ -1,25 +1,23 @@
+use bevy::ecs::IntoThreadLocalSystem;
 use bevy::prelude::*;
 use bevy_fly_camera::*;
 
+use bevy_prefab_editor::plugin::*;
 use bevy_prefab_editor::*;
 
 fn main() {
     App::build()
         .add_default_plugins()
         .add_plugin(FlyCameraPlugin)
-        .register_component::<Asset<Mesh>>()
-        .register_component::<IntoAsset<Color, StandardMaterial>>()
-        .register_component::<DefaultComponent<GlobalTransform>>()
-        .add_startup_system(setup.system())
-        .add_system_to_stage(stage::LAST, load_asset_system::<Mesh>.system())
-        .add_system_to_stage(
-            stage::LAST,
-            into_asset_system::<Color, StandardMaterial>.system(),
-        )
-        .add_system_to_stage(
-            stage::LAST,
-            default_component_system::<GlobalTransform>.system(),
+        .add_plugin(
+            PrefabPlugin::new()
+                .asset::<Mesh>()
+                .into_asset::<Color, StandardMaterial>()
+                .material()
+                .default_component::<GlobalTransform>(),
         )
+        .add_startup_system(setup.system())
+        .add_system(save_world_system.thread_local_system())
         .run();
 }
 
@@ -36,3 +34,10 @@ fn setup(
     scene_spawner.spawn(handle);
     asset_server.watch_for_changes().unwrap();
 }
+
+fn save_world_system(world: &mut World, resources: &mut Resources) {
+    let input = resources.get::<Input<KeyCode>>().unwrap();
+    if input.pressed(KeyCode::LControl) && input.just_pressed(KeyCode::S) {
+        write_world_prefab("assets/prefab_world.scn", world, resources).unwrap();
+    }
+}
@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use super::search::fuzzy_score;
+
+pub type ActionFn = Arc<dyn Fn(&mut World, &mut Resources) + Send + Sync + 'static>;
+
+/// Something the editor can do, listed in the command palette.
+///
+/// Actions whose id is also a `Keymap` action are triggered by its keys.
+#[derive(Clone)]
+pub struct EditorAction {
+    pub id: String,
+    pub name: String,
+    run: ActionFn,
+}
+
+impl EditorAction {
+    pub fn new<I, N, F>(id: I, name: N, run: F) -> Self
+    where
+        I: Into<String>,
+        N: Into<String>,
+        F: Fn(&mut World, &mut Resources) + Send + Sync + 'static,
+    {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            run: Arc::new(run),
+        }
+    }
+}
+
+impl std::fmt::Debug for EditorAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("EditorAction")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+/// Registry of `EditorAction`s, run with exclusive access to the world.
+#[derive(Debug, Default)]
+pub struct EditorActions {
+    actions: Vec<EditorAction>,
+    queued: Vec<String>,
+}
+
+impl EditorActions {
+    /// Registers an action, replacing any action with the same id.
+    pub fn add(&mut self, action: EditorAction) -> &mut Self {
+        self.remove(&action.id);
+        self.actions.push(action);
+        self
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<EditorAction> {
+        let index = self.actions.iter().position(|action| action.id == id)?;
+        Some(self.actions.remove(index))
+    }
+
+    /// Removes every action whose id starts with `prefix`.
+    pub fn remove_prefixed(&mut self, prefix: &str) {
+        self.actions.retain(|action| !action.id.starts_with(prefix));
+    }
+
+    pub fn get(&self, id: &str) -> Option<&EditorAction> {
+        self.actions.iter().find(|action| action.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &EditorAction> {
+        self.actions.iter()
+    }
+
+    /// Actions whose name fuzzily matches `pattern`, best match first.
+    pub fn search(&self, pattern: &str) -> Vec<&EditorAction> {
+        let mut matches = self
+            .actions
+            .iter()
+            .filter_map(|action| fuzzy_score(pattern, &action.name).map(|score| (score, action)))
+            .collect::<Vec<_>>();
+        matches.sort_by(|(a_score, a), (b_score, b)| {
+            b_score.cmp(a_score).then_with(|| a.name.cmp(&b.name))
+        });
+        matches.into_iter().map(|(_, action)| action).collect()
+    }
+
+    /// Runs the action with the given id during the next `run_actions_system`.
+    pub fn queue<S: Into<String>>(&mut self, id: S) {
+        self.queued.push(id.into());
+    }
+
+    /// The queued actions that are still registered; unknown ids in the keymap are
+    /// reported once by `keymap_check_system` instead.
+    fn take_queued(&mut self) -> Vec<ActionFn> {
+        let actions = &self.actions;
+        self.queued
+            .drain(..)
+            .filter_map(|id| actions.iter().find(|action| action.id == id))
+            .map(|action| action.run.clone())
+            .collect()
+    }
+}
+
+/// Runs the queued `EditorActions`.
+pub fn run_actions_system(world: &mut World, resources: &mut Resources) {
+    let queued = resources
+        .get_mut::<EditorActions>()
+        .unwrap()
+        .take_queued();
+    for run in queued {
+        run(world, resources);
+    }
+}
+
+/// Lets plugins register their own editor actions.
+pub trait EditorActionsExt {
+    fn add_editor_action(&mut self, action: EditorAction) -> &mut Self;
+}
+
+impl EditorActionsExt for AppBuilder {
+    fn add_editor_action(&mut self, action: EditorAction) -> &mut Self {
+        if self.resources().get::<EditorActions>().is_none() {
+            self.init_resource::<EditorActions>();
+        }
+        self.resources_mut()
+            .get_mut::<EditorActions>()
+            .unwrap()
+            .add(action);
+        self
+    }
+}
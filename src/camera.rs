@@ -0,0 +1,335 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use bevy::render::mesh::{VertexAttribute, VertexAttributeValues};
+
+use super::gizmo::Ray;
+
+/// Steepest pitch of the orbit camera, in radians, short of looking straight up or down.
+pub const MAX_PITCH: f32 = 89.0 * std::f32::consts::PI / 180.0;
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn point(point: Vec3) -> Self {
+        Self {
+            min: point,
+            max: point,
+        }
+    }
+
+    /// The smallest box containing `points`, or `None` if there are none.
+    pub fn from_points<I: IntoIterator<Item = Vec3>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = Self::point(points.next()?);
+        Some(points.fold(first, |aabb, point| aabb.merge(&Self::point(point))))
+    }
+
+    /// The bounds of a mesh's vertex positions, in the mesh's space.
+    pub fn from_mesh(mesh: &Mesh) -> Option<Self> {
+        let positions = mesh
+            .attributes
+            .iter()
+            .find(|attribute| attribute.name == VertexAttribute::POSITION)?;
+        match &positions.values {
+            VertexAttributeValues::Float3(positions) => {
+                Self::from_points(positions.iter().map(|&position| Vec3::from(position)))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn merge(&self, other: &Aabb) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// The bounds of this box after transforming it by `transform`.
+    pub fn transformed(&self, transform: &Mat4) -> Self {
+        let corners = (0..8).map(|corner| {
+            let pick = |bit: u32, min: f32, max: f32| if corner & bit == 0 { min } else { max };
+            transform.transform_point3(Vec3::new(
+                pick(1, self.min.x(), self.max.x()),
+                pick(2, self.min.y(), self.max.y()),
+                pick(4, self.min.z(), self.max.z()),
+            ))
+        });
+        Self::from_points(corners).unwrap()
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Radius of the sphere around `center` containing the box.
+    pub fn radius(&self) -> f32 {
+        (self.max - self.min).length() * 0.5
+    }
+
+    /// Distance along `ray` to where it enters this box transformed by `transform`, if it
+    /// hits it.
+    pub fn ray_hit(&self, ray: &Ray, transform: &Mat4) -> Option<f32> {
+        // the ray is brought into the box's space, where distances along it stay the same
+        let inverse = transform.inverse();
+        let origin = inverse.transform_point3(ray.origin);
+        let direction = inverse.transform_vector3(ray.direction);
+        let mut near = std::f32::NEG_INFINITY;
+        let mut far = std::f32::INFINITY;
+        for &(origin, direction, min, max) in &[
+            (origin.x(), direction.x(), self.min.x(), self.max.x()),
+            (origin.y(), direction.y(), self.min.y(), self.max.y()),
+            (origin.z(), direction.z(), self.min.z(), self.max.z()),
+        ] {
+            if direction == 0.0 {
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+            let (a, b) = ((min - origin) / direction, (max - origin) / direction);
+            near = near.max(a.min(b));
+            far = far.min(a.max(b));
+        }
+        if near > far || far < 0.0 {
+            None
+        } else {
+            Some(near.max(0.0))
+        }
+    }
+}
+
+/// How far from the center of a sphere of `radius` a perspective camera with a vertical
+/// field of view of `fov` radians has to be to fit it in both directions.
+pub fn frame_distance(radius: f32, fov: f32, aspect_ratio: f32) -> f32 {
+    let horizontal_fov = 2.0 * ((fov * 0.5).tan() * aspect_ratio).atan();
+    radius / (fov.min(horizontal_fov) * 0.5).sin()
+}
+
+/// Half the height an orthographic camera has to see to fit a sphere of `radius` in both
+/// directions.
+pub fn frame_half_height(radius: f32, aspect_ratio: f32) -> f32 {
+    radius.max(radius / aspect_ratio)
+}
+
+/// An orthographic projection seeing `half_height` above and below the center of the view,
+/// and `depth` in front of and behind the camera.
+pub fn orthographic_projection(half_height: f32, aspect_ratio: f32, depth: f32) -> Mat4 {
+    let half_width = half_height * aspect_ratio;
+    Mat4::orthographic_rh(
+        -half_width,
+        half_width,
+        -half_height,
+        half_height,
+        -depth,
+        depth,
+    )
+}
+
+/// An axis-aligned view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrthoView {
+    /// Looking down -Z.
+    Front,
+    /// Looking down -X.
+    Side,
+    /// Looking down -Y, with -Z up.
+    Top,
+}
+
+impl OrthoView {
+    /// Yaw and pitch of the view, as in `look_angles`.
+    pub fn angles(self) -> (f32, f32) {
+        match self {
+            OrthoView::Front => (0.0, 0.0),
+            OrthoView::Side => (FRAC_PI_2, 0.0),
+            OrthoView::Top => (0.0, -FRAC_PI_2),
+        }
+    }
+}
+
+/// Yaw and pitch, in radians, of a camera looking along `forward`.
+///
+/// Yaw turns around Y starting from looking down -Z, pitch is positive looking up.
+pub fn look_angles(forward: Vec3) -> (f32, f32) {
+    let forward = forward.normalize();
+    let pitch = forward.y().max(-1.0).min(1.0).asin();
+    let yaw = (-forward.x()).atan2(-forward.z());
+    (yaw, pitch)
+}
+
+/// Rotation of a camera with the given `look_angles`.
+pub fn look_rotation(yaw: f32, pitch: f32) -> Quat {
+    Quat::from_rotation_y(yaw) * Quat::from_rotation_x(pitch)
+}
+
+/// A camera circling `focus`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Orbit {
+    pub focus: Vec3,
+    /// In radians, as in `look_angles`.
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+}
+
+impl Default for Orbit {
+    fn default() -> Self {
+        Self {
+            focus: Vec3::zero(),
+            yaw: 0.0,
+            pitch: 0.0,
+            distance: 10.0,
+        }
+    }
+}
+
+impl Orbit {
+    /// The orbit a camera at `eye` looking at `focus` is on.
+    pub fn looking_at(eye: Vec3, focus: Vec3) -> Self {
+        let (yaw, pitch) = look_angles(focus - eye);
+        Self {
+            focus,
+            yaw,
+            pitch: pitch.max(-MAX_PITCH).min(MAX_PITCH),
+            distance: (focus - eye).length(),
+        }
+    }
+
+    pub fn rotation(&self) -> Quat {
+        look_rotation(self.yaw, self.pitch)
+    }
+
+    pub fn eye(&self) -> Vec3 {
+        self.focus - self.rotation() * -Vec3::unit_z() * self.distance
+    }
+
+    /// Turns the orbit by `yaw` and `pitch` radians, keeping it from flipping over.
+    pub fn turn(&mut self, yaw: f32, pitch: f32) {
+        self.yaw += yaw;
+        self.pitch = (self.pitch + pitch).max(-MAX_PITCH).min(MAX_PITCH);
+    }
+}
+
+/// Eases `t` from 0 to 1, slowing down at the end.
+pub fn ease_out(t: f32) -> f32 {
+    let t = t.max(0.0).min(1.0);
+    1.0 - (1.0 - t).powi(3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn approx_vec(a: Vec3, b: Vec3) -> bool {
+        (a - b).length() < 1e-4
+    }
+
+    fn unit_box() -> Aabb {
+        Aabb {
+            min: -Vec3::one(),
+            max: Vec3::one(),
+        }
+    }
+
+    #[test]
+    fn ray_hits_transformed_box() {
+        let ray = Ray::new(Vec3::zero(), -Vec3::unit_z());
+        let transform = Mat4::from_translation(Vec3::new(0.0, 0.0, -5.0));
+        assert!(approx(unit_box().ray_hit(&ray, &transform).unwrap(), 4.0));
+
+        let scaled = transform * Mat4::from_scale(Vec3::splat(2.0));
+        assert!(approx(unit_box().ray_hit(&ray, &scaled).unwrap(), 3.0));
+    }
+
+    #[test]
+    fn ray_inside_box_hits_at_its_origin() {
+        let ray = Ray::new(Vec3::zero(), Vec3::unit_x());
+        assert_eq!(unit_box().ray_hit(&ray, &Mat4::identity()), Some(0.0));
+    }
+
+    #[test]
+    fn ray_misses_box() {
+        let transform = Mat4::from_translation(Vec3::new(0.0, 0.0, -5.0));
+        let beside = Ray::new(Vec3::new(2.0, 0.0, 0.0), -Vec3::unit_z());
+        assert_eq!(unit_box().ray_hit(&beside, &transform), None);
+        let away = Ray::new(Vec3::zero(), Vec3::unit_z());
+        assert_eq!(unit_box().ray_hit(&away, &transform), None);
+    }
+
+    #[test]
+    fn transformed_box_contains_the_rotated_corners() {
+        let aabb = Aabb {
+            min: Vec3::zero(),
+            max: Vec3::new(1.0, 2.0, 3.0),
+        };
+        let transform = Mat4::from_rotation_translation(
+            Quat::from_rotation_y(FRAC_PI_2),
+            Vec3::new(1.0, 0.0, 0.0),
+        );
+        let transformed = aabb.transformed(&transform);
+        assert!(approx_vec(transformed.min, Vec3::new(1.0, 0.0, -1.0)));
+        assert!(approx_vec(transformed.max, Vec3::new(4.0, 2.0, 0.0)));
+    }
+
+    #[test]
+    fn frame_distance_fits_the_narrower_fov() {
+        let fov = FRAC_PI_2;
+        let square = frame_distance(1.0, fov, 1.0);
+        assert!(approx(square, 2.0f32.sqrt()));
+        assert!(approx(frame_distance(1.0, fov, 2.0), square));
+        assert!(frame_distance(1.0, fov, 0.5) > square);
+    }
+
+    #[test]
+    fn frame_half_height_fits_the_narrower_side() {
+        assert!(approx(frame_half_height(1.0, 2.0), 1.0));
+        assert!(approx(frame_half_height(1.0, 0.5), 2.0));
+    }
+
+    #[test]
+    fn look_angles_invert_look_rotation() {
+        for &(yaw, pitch) in &[(0.0, 0.0), (FRAC_PI_2, 0.3), (-2.5, -1.2), (1.0, 1.5)] {
+            let forward = look_rotation(yaw, pitch) * -Vec3::unit_z();
+            let (found_yaw, found_pitch) = look_angles(forward);
+            assert!(approx(found_yaw, yaw), "yaw {} != {}", found_yaw, yaw);
+            assert!(
+                approx(found_pitch, pitch),
+                "pitch {} != {}",
+                found_pitch,
+                pitch
+            );
+        }
+    }
+
+    #[test]
+    fn orbit_eye_is_behind_the_focus() {
+        let orbit = Orbit::default();
+        assert!(approx_vec(orbit.eye(), Vec3::new(0.0, 0.0, 10.0)));
+
+        let eye = Vec3::new(3.0, 4.0, 5.0);
+        let orbit = Orbit::looking_at(eye, Vec3::new(1.0, 0.0, -1.0));
+        assert!(approx_vec(orbit.eye(), eye));
+    }
+
+    #[test]
+    fn orbit_turn_clamps_pitch() {
+        let mut orbit = Orbit::default();
+        orbit.turn(0.5, 10.0);
+        assert!(approx(orbit.yaw, 0.5));
+        assert!(approx(orbit.pitch, MAX_PITCH));
+        orbit.turn(0.5, -20.0);
+        assert!(approx(orbit.yaw, 1.0));
+        assert!(approx(orbit.pitch, -MAX_PITCH));
+    }
+}
@@ -53,6 +53,10 @@ impl DisplayWrapper<KeyCode> {
             X => "x",
             Y => "y",
             Z => "z",
+            Period => ".",
+            Comma => ",",
+            Minus | Subtract => "-",
+            Space => " ",
             _ => "",
         }
     }
@@ -1,9 +1,19 @@
 use bevy::prelude::*;
 use bevy_mod_picking::*;
 
+use super::numeric::parse_numeric;
+
 #[derive(Default, Debug, Clone, Copy)]
 pub struct Widget(pub u32);
 
+/// Whether a widget is selected.
+///
+/// Follows `SelectablePickMesh` for clicks in the viewport, but can also be set by the
+/// editor's panels, which `SelectablePickMesh` doesn't allow; those only reset the
+/// `SelectablePickMesh` of widgets they deselect.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Selected(pub bool);
+
 #[derive(Bundle)]
 pub struct WidgetComponents {
     pub widget: Widget,
@@ -12,6 +22,7 @@ pub struct WidgetComponents {
     pub pickable: PickableMesh,
     pub highlight: HighlightablePickMesh,
     pub select: SelectablePickMesh,
+    pub selected: Selected,
 }
 
 impl WidgetComponents {
@@ -23,13 +34,374 @@ impl WidgetComponents {
             pickable: Default::default(),
             highlight: Default::default(),
             select: Default::default(),
+            selected: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransformMode {
+    Translate,
+    Rotate,
+    Scale,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub fn unit(self) -> Vec3 {
+        match self {
+            Axis::X => Vec3::unit_x(),
+            Axis::Y => Vec3::unit_y(),
+            Axis::Z => Vec3::unit_z(),
+        }
+    }
+
+    /// The two other axes, in order.
+    pub fn others(self) -> [Axis; 2] {
+        match self {
+            Axis::X => [Axis::Y, Axis::Z],
+            Axis::Y => [Axis::X, Axis::Z],
+            Axis::Z => [Axis::X, Axis::Y],
+        }
+    }
+}
+
+/// The axes a transform is limited to, in its `TransformSpace`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Constraint {
+    pub axis: Option<Axis>,
+    /// Constrains to the plane perpendicular to `axis` instead of to `axis` itself.
+    pub plane: bool,
+}
+
+impl Constraint {
+    pub fn axis(axis: Axis) -> Self {
+        Self {
+            axis: Some(axis),
+            plane: false,
+        }
+    }
+
+    pub fn plane(axis: Axis) -> Self {
+        Self {
+            axis: Some(axis),
+            plane: true,
+        }
+    }
+
+    /// The axes moved along: `axis`, the two others for a plane, or none at all.
+    pub fn axes(&self) -> Vec<Axis> {
+        match self.axis {
+            Some(axis) if self.plane => axis.others().to_vec(),
+            Some(axis) => vec![axis],
+            None => vec![],
+        }
+    }
+}
+
+/// The basis that `Axis` constraints are expressed in.
+///
+/// Scaling is always along the entity's own axes, whatever the space, because a `Transform`
+/// can't hold a scale along other axes without shearing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransformSpace {
+    Global,
+    /// The axes of the transformed entity.
+    Local,
+    /// The axes of the camera, with X to the right and Y up.
+    View,
+}
+
+impl TransformSpace {
+    pub fn next(self) -> Self {
+        match self {
+            TransformSpace::Global => TransformSpace::Local,
+            TransformSpace::Local => TransformSpace::View,
+            TransformSpace::View => TransformSpace::Global,
+        }
+    }
+
+    /// Rotation taking the global axes to the axes of this space.
+    pub fn rotation(self, entity: Quat, camera: Quat) -> Quat {
+        match self {
+            TransformSpace::Global => Quat::identity(),
+            TransformSpace::Local => entity,
+            TransformSpace::View => camera,
+        }
+    }
+}
+
+impl Default for TransformSpace {
+    fn default() -> Self {
+        TransformSpace::Global
+    }
+}
+
+/// Smallest factor a scale can add up to, so that scaling never collapses or flips an
+/// entity.
+pub const MIN_SCALE: f32 = 0.01;
+
+/// Scale factors for scaling by `factor` along `axes`, or along all of them if `axes` is
+/// empty.
+pub fn scale_factors(factor: f32, axes: &[Axis]) -> Vec3 {
+    if axes.is_empty() {
+        return Vec3::splat(factor);
+    }
+    axes.iter()
+        .fold(Vec3::one(), |factors, axis| factors + axis.unit() * (factor - 1.0))
+}
+
+/// The factor that a mouse motion of `delta` scales by, when the scale so far is `total`.
+///
+/// Motion is relative to 1, so no motion doesn't scale, and the resulting scale is kept at
+/// `MIN_SCALE` or above.
+pub fn mouse_scale_factor(total: f32, delta: f32) -> f32 {
+    let total = total.max(MIN_SCALE);
+    (total * (1.0 + delta)).max(MIN_SCALE) / total
+}
+
+/// The event moving the selection by `delta` along the axes of `constraint`.
+///
+/// `basis` rotates the global axes into the transform's `TransformSpace`, and is ignored
+/// when scaling, which is along the entity's own axes.  A plane
+/// constraint translates and scales along the two other axes and rotates around the
+/// excluded one.  When scaling, `delta` is a factor.
+pub fn transform_event(
+    transform: TransformMode,
+    constraint: Constraint,
+    basis: Quat,
+    delta: f32,
+) -> Option<EditorEvent> {
+    let axes = constraint.axes();
+    let event = match transform {
+        TransformMode::Translate => {
+            constraint.axis?;
+            EditorEvent::Translate(
+                axes.iter()
+                    .fold(Vec3::zero(), |sum, axis| sum + basis * axis.unit())
+                    * delta,
+            )
+        }
+        TransformMode::Rotate => {
+            EditorEvent::Rotate(Quat::from_axis_angle(basis * constraint.axis?.unit(), delta))
+        }
+        // scaling without an axis is uniform
+        TransformMode::Scale => EditorEvent::Scale(scale_factors(delta, &axes)),
+    };
+    Some(event)
+}
+
+/// The value of a transform that a typed `entry` asks for, as passed to `transform_event`
+/// in total.
+///
+/// `origin` is the translation and scale of the selection when the value was started, for
+/// absolute values.  Scales have to be positive and are kept at `MIN_SCALE` or above.
+pub fn entry_target(
+    entry: &str,
+    transform: TransformMode,
+    constraint: Constraint,
+    basis: Quat,
+    origin: (Vec3, Vec3),
+) -> Result<f32, String> {
+    let parsed = parse_numeric(entry, transform == TransformMode::Rotate)
+        .map_err(|err| err.to_string())?;
+    let target = if !parsed.absolute {
+        parsed.value
+    } else {
+        let axis = match constraint.axis {
+            Some(axis) if !constraint.plane => axis,
+            _ => return Err("absolute values need a single axis".to_string()),
+        };
+        match transform {
+            TransformMode::Translate => parsed.value - origin.0.dot(basis * axis.unit()),
+            TransformMode::Scale => parsed.value / origin.1.dot(axis.unit()),
+            TransformMode::Rotate => {
+                return Err("rotations can't be absolute".to_string());
+            }
+        }
+    };
+    if transform == TransformMode::Scale {
+        if !(target > 0.0 && target.is_finite()) {
+            return Err("scale has to be positive".to_string());
         }
+        return Ok(target.max(MIN_SCALE));
     }
+    Ok(target)
 }
 
+/// A change to the selected entity's transform.
+///
+/// Translations and rotation axes are in world space, scale factors are along the entity's
+/// own axes.
 #[derive(Debug, Clone, Copy)]
 pub enum EditorEvent {
     Translate(Vec3),
     Rotate(Quat),
     Scale(Vec3),
 }
+
+/// Screen-space badge that follows a widget whose asset failed to load.
+#[derive(Debug, Clone, Copy)]
+pub struct WarningBadge(pub Entity);
+
+/// Projects a world-space point into window coordinates, with the origin in the bottom left.
+///
+/// Returns `None` if the point is behind the camera.
+pub fn world_to_screen(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    window_size: Vec2,
+    point: Vec3,
+) -> Option<Vec2> {
+    let view = camera_transform.value().inverse();
+    let clip = camera.projection_matrix * view * point.extend(1.0);
+    if clip.w() <= 0.0 {
+        return None;
+    }
+    let ndc = clip.truncate() / clip.w();
+    Some(Vec2::new(
+        (ndc.x() + 1.0) * 0.5 * window_size.x(),
+        (ndc.y() + 1.0) * 0.5 * window_size.y(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    fn scale_event(constraint: Constraint, factor: f32) -> Vec3 {
+        match transform_event(TransformMode::Scale, constraint, Quat::identity(), factor) {
+            Some(EditorEvent::Scale(factors)) => factors,
+            event => panic!("expected a scale, got {:?}", event),
+        }
+    }
+
+    #[test]
+    fn scale_without_axis_is_uniform() {
+        assert_eq!(scale_factors(2.0, &[]), Vec3::splat(2.0));
+        assert_eq!(scale_event(Constraint::default(), 0.5), Vec3::splat(0.5));
+    }
+
+    #[test]
+    fn scale_along_axis() {
+        assert_eq!(
+            scale_event(Constraint::axis(Axis::Y), 3.0),
+            Vec3::new(1.0, 3.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn scale_in_plane_leaves_excluded_axis() {
+        assert_eq!(
+            scale_event(Constraint::plane(Axis::Z), 2.0),
+            Vec3::new(2.0, 2.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn translate_needs_an_axis() {
+        let event = transform_event(
+            TransformMode::Translate,
+            Constraint::default(),
+            Quat::identity(),
+            1.0,
+        );
+        assert!(event.is_none());
+    }
+
+    #[test]
+    fn translate_in_basis() {
+        let basis = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        match transform_event(TransformMode::Translate, Constraint::axis(Axis::X), basis, 2.0) {
+            Some(EditorEvent::Translate(offset)) => {
+                assert!((offset - Vec3::new(0.0, 2.0, 0.0)).length() < 1e-5)
+            }
+            event => panic!("expected a translation, got {:?}", event),
+        }
+    }
+
+    #[test]
+    fn no_mouse_motion_does_not_scale() {
+        assert_eq!(mouse_scale_factor(1.0, 0.0), 1.0);
+        assert!(approx(mouse_scale_factor(0.3, 0.0), 1.0));
+    }
+
+    #[test]
+    fn mouse_scale_settles_at_minimum() {
+        let mut total = 1.0;
+        for &delta in &[-0.5, -0.9, -2.0, -10.0].repeat(10) {
+            total *= mouse_scale_factor(total, delta);
+            assert!(total > 0.0);
+            assert!(total >= MIN_SCALE - 1e-6);
+        }
+        assert!(approx(total, MIN_SCALE));
+    }
+
+    #[test]
+    fn mouse_scale_recovers_from_minimum() {
+        let total = MIN_SCALE;
+        assert!(mouse_scale_factor(total, 0.5) > 1.0);
+    }
+
+    fn scale_entry(entry: &str) -> Result<f32, String> {
+        entry_target(
+            entry,
+            TransformMode::Scale,
+            Constraint::axis(Axis::X),
+            Quat::identity(),
+            (Vec3::zero(), Vec3::new(2.0, 1.0, 1.0)),
+        )
+    }
+
+    #[test]
+    fn typed_scale_must_be_positive() {
+        assert!(scale_entry("=0").is_err());
+        assert!(scale_entry("0").is_err());
+        assert!(scale_entry("-2").is_err());
+    }
+
+    #[test]
+    fn typed_scale_is_clamped() {
+        assert_eq!(scale_entry("0.001"), Ok(MIN_SCALE));
+        assert_eq!(scale_entry("1.5"), Ok(1.5));
+    }
+
+    #[test]
+    fn typed_absolute_scale_is_relative_to_origin() {
+        assert_eq!(scale_entry("=1"), Ok(0.5));
+    }
+
+    #[test]
+    fn typed_absolute_translation() {
+        let target = entry_target(
+            "=3",
+            TransformMode::Translate,
+            Constraint::axis(Axis::Y),
+            Quat::identity(),
+            (Vec3::new(0.0, 1.0, 0.0), Vec3::one()),
+        );
+        assert_eq!(target, Ok(2.0));
+    }
+
+    #[test]
+    fn absolute_values_need_a_single_axis() {
+        let target = entry_target(
+            "=3",
+            TransformMode::Translate,
+            Constraint::plane(Axis::Y),
+            Quat::identity(),
+            (Vec3::zero(), Vec3::one()),
+        );
+        assert!(target.is_err());
+    }
+}
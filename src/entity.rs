@@ -13,6 +13,10 @@ use bevy::render::{
 use bevy::type_registry::*;
 use hashbrown::HashMap;
 
+use super::material::MaterialDescription;
+use super::outliner::short_name;
+use super::plugin::short_type_name;
+use super::search::fuzzy_search;
 use super::*;
 
 pub trait ComponentsExt {
@@ -150,7 +154,7 @@ impl DefaultBundles {
                 .push(Asset::<Mesh>::default().to_dynamic());
             bundle
                 .components
-                .push(IntoAsset::<Color, StandardMaterial>::default().to_dynamic());
+                .push(MaterialDescription::default().to_dynamic());
             bundle.components.push(Draw::default().to_dynamic());
             bundle.components.push(MainPass::default().to_dynamic());
             bundle.components.push(
@@ -187,6 +191,15 @@ impl DefaultBundles {
         self.map.get(name).cloned()
     }
 
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.map.keys().map(String::as_str)
+    }
+
+    /// Names of the bundles fuzzily matching `pattern`, best match first.
+    pub fn search(&self, pattern: &str) -> Vec<&str> {
+        fuzzy_search(pattern, self.names())
+    }
+
     pub fn to_dynamic(&self) -> DynamicProperties {
         let props = self
             .map
@@ -238,28 +251,19 @@ pub struct DefaultProperties {
 
 impl DefaultProperties {
     pub fn new() -> Self {
-        let mut map = HashMap::new();
-        map.insert("Light".to_string(), Light::default().to_dynamic());
-        map.insert("Transform".to_string(), Transform::default().to_dynamic());
-        map.insert(
-            "GlobalTransform".to_string(),
-            DefaultComponent::<GlobalTransform>::default().to_dynamic(),
-        );
-        map.insert(
-            "Asset<Mesh>".to_string(),
-            Asset::<Mesh>::default().to_dynamic(),
-        );
-        map.insert(
-            "Into<Color, StandardMaterial>".to_string(),
-            IntoAsset::<Color, StandardMaterial>::default().to_dynamic(),
-        );
-        map.insert("Draw".to_string(), Draw::default().to_dynamic());
-        map.insert("MainPass".to_string(), MainPass::default().to_dynamic());
-        map.insert(
-            "RenderPipelines".to_string(),
-            RenderPipelines::default().to_dynamic(),
-        );
-        Self { map }
+        let mut properties = Self {
+            map: HashMap::new(),
+        };
+        properties.insert_default::<Light>();
+        properties.insert_default::<Transform>();
+        properties.insert_default::<DefaultComponent<GlobalTransform>>();
+        properties.insert_default::<Asset<Mesh>>();
+        properties.insert_default::<IntoAsset<Color, StandardMaterial>>();
+        properties.insert_default::<MaterialDescription>();
+        properties.insert_default::<Draw>();
+        properties.insert_default::<MainPass>();
+        properties.insert_default::<RenderPipelines>();
+        properties
     }
 
     pub fn get(&self, name: &str) -> Option<DynamicProperties> {
@@ -268,6 +272,30 @@ impl DefaultProperties {
             .map(|props| props.clone_prop().as_properties().unwrap().to_dynamic())
     }
 
+    pub fn insert<S: Into<String>>(&mut self, name: S, props: DynamicProperties) {
+        self.map.insert(name.into(), props);
+    }
+
+    /// Adds `T::default()` under `T`'s short type name, such as
+    /// `DefaultComponent<GlobalTransform>`, which is the key every entry is stored under.
+    pub fn insert_default<T: Properties + Default>(&mut self) {
+        self.insert(short_type_name::<T>(), T::default().to_dynamic());
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.map.keys().map(String::as_str)
+    }
+
+    /// Names of the components fuzzily matching `pattern`, best match first.
+    pub fn search(&self, pattern: &str) -> Vec<&str> {
+        fuzzy_search(pattern, self.names())
+    }
+
+    /// Adds every entry of `other`, replacing entries with the same name.
+    pub fn extend(&mut self, other: DefaultProperties) {
+        self.map.extend(other.map);
+    }
+
     pub fn to_dynamic(&self) -> DynamicProperties {
         let props = self.map.iter().map(|(_, comp)| comp.clone_prop()).collect();
         let prop_names = self
@@ -309,7 +337,7 @@ impl DefaultProperties {
                     prop_indices,
                     property_type,
                 };
-                (prop.type_name.clone(), prop)
+                (short_name(&prop.type_name), prop)
             })
             .collect();
         Self { map }
@@ -338,3 +366,28 @@ impl Default for DefaultProperties {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_properties_keep_their_keys_through_a_round_trip() {
+        let properties = DefaultProperties::new();
+        let restored = DefaultProperties::from_dynamic(&properties.to_dynamic());
+        let mut names = properties.names().collect::<Vec<_>>();
+        let mut restored_names = restored.names().collect::<Vec<_>>();
+        names.sort();
+        restored_names.sort();
+        assert_eq!(names, restored_names);
+    }
+
+    #[test]
+    fn default_properties_are_keyed_by_the_registered_wrapper() {
+        let properties = DefaultProperties::new();
+        assert!(properties.get("DefaultComponent<GlobalTransform>").is_some());
+        assert!(properties.get("IntoAsset<Color, StandardMaterial>").is_some());
+        assert!(properties.get("Asset<Mesh>").is_some());
+        assert!(properties.get("GlobalTransform").is_none());
+    }
+}
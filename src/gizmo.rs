@@ -0,0 +1,319 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use bevy::prelude::*;
+use bevy::render::mesh::VertexAttribute;
+use bevy::render::pipeline::PrimitiveTopology;
+
+use super::editor::{Axis, TransformMode, MIN_SCALE};
+
+/// Size of the gizmo relative to its distance from the camera, so that it keeps the same
+/// size on screen.
+pub const GIZMO_SCALE: f32 = 0.15;
+
+const EPSILON: f32 = 1e-6;
+
+/// Part of the gizmo that can be dragged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GizmoHandle {
+    pub mode: TransformMode,
+    pub axis: Axis,
+}
+
+/// A half-line in world space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    /// Normalized.
+    pub direction: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    /// The ray under `cursor`, in window coordinates with the origin in the bottom left.
+    ///
+    /// Works for both perspective and orthographic projections, by unprojecting the cursor
+    /// at two depths with the inverse of the view-projection matrix.
+    pub fn from_screen(
+        projection: Mat4,
+        camera_transform: Mat4,
+        window_size: Vec2,
+        cursor: Vec2,
+    ) -> Option<Self> {
+        let ndc = Vec2::new(
+            cursor.x() / window_size.x() * 2.0 - 1.0,
+            cursor.y() / window_size.y() * 2.0 - 1.0,
+        );
+        let inverse = (projection * camera_transform.inverse()).inverse();
+        let unproject = |depth: f32| {
+            let point = inverse * ndc.extend(depth).extend(1.0);
+            if point.w().abs() < EPSILON {
+                None
+            } else {
+                Some(point.truncate() / point.w())
+            }
+        };
+        let near = unproject(0.0)?;
+        let far = unproject(1.0)?;
+        let direction = far - near;
+        if direction.length_squared() < EPSILON {
+            return None;
+        }
+        Some(Self::new(near, direction))
+    }
+
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /// Where the ray hits the plane through `point` with the given normal, if it does.
+    pub fn intersect_plane(&self, point: Vec3, normal: Vec3) -> Option<Vec3> {
+        let denominator = normal.dot(self.direction);
+        if denominator.abs() < EPSILON {
+            return None;
+        }
+        let distance = normal.dot(point - self.origin) / denominator;
+        if distance < 0.0 {
+            None
+        } else {
+            Some(self.at(distance))
+        }
+    }
+}
+
+/// Rotation taking the Y axis, along which the gizmo meshes are built, to `axis`.
+pub fn axis_rotation(axis: Axis) -> Quat {
+    match axis {
+        Axis::X => Quat::from_rotation_z(-FRAC_PI_2),
+        Axis::Y => Quat::identity(),
+        Axis::Z => Quat::from_rotation_x(FRAC_PI_2),
+    }
+}
+
+/// Normal of the plane containing `axis` that faces the camera the most.
+///
+/// Returns `None` when looking straight down the axis, where dragging along it is
+/// undefined.
+pub fn axis_plane_normal(axis: Vec3, view_direction: Vec3) -> Option<Vec3> {
+    let normal = axis.cross(view_direction).cross(axis);
+    if normal.length_squared() < EPSILON {
+        None
+    } else {
+        Some(normal.normalize())
+    }
+}
+
+/// Position of the point under `ray` along the line through `origin` in direction `axis`.
+pub fn axis_position(ray: &Ray, origin: Vec3, axis: Vec3) -> Option<f32> {
+    let normal = axis_plane_normal(axis, ray.direction)?;
+    let hit = ray.intersect_plane(origin, normal)?;
+    Some((hit - origin).dot(axis))
+}
+
+/// Angle of the point under `ray` around `axis`, on the plane through `origin`.
+pub fn ring_angle(ray: &Ray, origin: Vec3, axis: Vec3) -> Option<f32> {
+    let hit = ray.intersect_plane(origin, axis)?;
+    let offset = hit - origin;
+    let u = orthogonal(axis);
+    let v = axis.cross(u);
+    if offset.length_squared() < EPSILON {
+        return None;
+    }
+    Some(offset.dot(v).atan2(offset.dot(u)))
+}
+
+/// The signed difference between two angles, in `(-PI, PI]`.
+pub fn angle_delta(from: f32, to: f32) -> f32 {
+    let mut delta = (to - from) % (2.0 * PI);
+    if delta > PI {
+        delta -= 2.0 * PI;
+    } else if delta <= -PI {
+        delta += 2.0 * PI;
+    }
+    delta
+}
+
+/// How much a scale handle grabbed at `start` along its axis scales when dragged to
+/// `current`.
+///
+/// Dragging past the pivot is clamped to `MIN_SCALE` instead of flipping the selection.
+pub fn scale_factor(start: f32, current: f32) -> f32 {
+    if start.abs() < EPSILON {
+        1.0
+    } else {
+        (current / start).max(MIN_SCALE)
+    }
+}
+
+/// A unit vector perpendicular to `v`.
+fn orthogonal(v: Vec3) -> Vec3 {
+    let other = if v.x().abs() < 0.9 {
+        Vec3::unit_x()
+    } else {
+        Vec3::unit_y()
+    };
+    v.cross(other).normalize()
+}
+
+/// A torus around the Y axis, used for the rotation rings.
+pub fn ring_mesh(radius: f32, thickness: f32, segments: u32, sides: u32) -> Mesh {
+    let mut positions = vec![];
+    let mut normals = vec![];
+    let mut uvs = vec![];
+    for segment in 0..=segments {
+        let u = segment as f32 / segments as f32;
+        let (sin_u, cos_u) = (u * 2.0 * PI).sin_cos();
+        let center = Vec3::new(cos_u * radius, 0.0, sin_u * radius);
+        for side in 0..=sides {
+            let v = side as f32 / sides as f32;
+            let (sin_v, cos_v) = (v * 2.0 * PI).sin_cos();
+            let normal = Vec3::new(cos_u * cos_v, sin_v, sin_u * cos_v);
+            let position = center + normal * thickness;
+            positions.push([position.x(), position.y(), position.z()]);
+            normals.push([normal.x(), normal.y(), normal.z()]);
+            uvs.push([u, v]);
+        }
+    }
+
+    let mut indices = vec![];
+    for segment in 0..segments {
+        for side in 0..sides {
+            let a = segment * (sides + 1) + side;
+            let b = a + sides + 1;
+            indices.extend_from_slice(&[a, a + 1, b, b, a + 1, b + 1]);
+        }
+    }
+
+    Mesh {
+        primitive_topology: PrimitiveTopology::TriangleList,
+        attributes: vec![
+            VertexAttribute::position(positions),
+            VertexAttribute::normal(normals),
+            VertexAttribute::uv(uvs),
+        ],
+        indices: Some(indices),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn approx_vec(a: Vec3, b: Vec3) -> bool {
+        (a - b).length() < 1e-4
+    }
+
+    #[test]
+    fn centre_of_screen_looks_forward() {
+        let projection = Mat4::perspective_rh(FRAC_PI_2 / 2.0, 4.0 / 3.0, 0.1, 100.0);
+        let camera = Mat4::from_translation(Vec3::new(0.0, 0.0, 5.0));
+        let ray = Ray::from_screen(
+            projection,
+            camera,
+            Vec2::new(800.0, 600.0),
+            Vec2::new(400.0, 300.0),
+        )
+        .unwrap();
+        assert!(approx_vec(ray.direction, -Vec3::unit_z()));
+        assert!(approx(ray.origin.x(), 0.0) && approx(ray.origin.y(), 0.0));
+    }
+
+    #[test]
+    fn orthographic_rays_are_parallel() {
+        let projection = Mat4::orthographic_rh(-4.0, 4.0, -3.0, 3.0, 0.1, 100.0);
+        let camera = Mat4::from_translation(Vec3::new(0.0, 0.0, 5.0));
+        let ray = Ray::from_screen(
+            projection,
+            camera,
+            Vec2::new(800.0, 600.0),
+            Vec2::new(600.0, 300.0),
+        )
+        .unwrap();
+        assert!(approx_vec(ray.direction, -Vec3::unit_z()));
+        assert!(approx(ray.origin.x(), 2.0));
+    }
+
+    #[test]
+    fn parallel_ray_misses_plane() {
+        let ray = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::unit_x());
+        assert_eq!(ray.intersect_plane(Vec3::zero(), Vec3::unit_y()), None);
+    }
+
+    #[test]
+    fn plane_behind_ray_is_missed() {
+        let ray = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::unit_y());
+        assert_eq!(ray.intersect_plane(Vec3::zero(), Vec3::unit_y()), None);
+    }
+
+    #[test]
+    fn ray_hits_plane() {
+        let ray = Ray::new(Vec3::new(1.0, 2.0, 0.0), -Vec3::unit_y());
+        let hit = ray.intersect_plane(Vec3::zero(), Vec3::unit_y()).unwrap();
+        assert!(approx_vec(hit, Vec3::new(1.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn position_along_axis() {
+        let ray = Ray::new(Vec3::new(2.0, 0.5, 5.0), -Vec3::unit_z());
+        let position = axis_position(&ray, Vec3::zero(), Vec3::unit_x()).unwrap();
+        assert!(approx(position, 2.0));
+    }
+
+    #[test]
+    fn looking_down_axis_has_no_position() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), -Vec3::unit_z());
+        assert_eq!(axis_position(&ray, Vec3::zero(), Vec3::unit_z()), None);
+    }
+
+    #[test]
+    fn ring_angles() {
+        let up = Ray::new(Vec3::new(0.0, 1.0, 5.0), -Vec3::unit_z());
+        let left = Ray::new(Vec3::new(-1.0, 0.0, 5.0), -Vec3::unit_z());
+        let up = ring_angle(&up, Vec3::zero(), Vec3::unit_z()).unwrap();
+        let left = ring_angle(&left, Vec3::zero(), Vec3::unit_z()).unwrap();
+        assert!(approx(angle_delta(up, left), FRAC_PI_2));
+    }
+
+    #[test]
+    fn ring_centre_has_no_angle() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), -Vec3::unit_z());
+        assert_eq!(ring_angle(&ray, Vec3::zero(), Vec3::unit_z()), None);
+    }
+
+    #[test]
+    fn angle_delta_wraps() {
+        assert!(approx(angle_delta(0.5, 1.0), 0.5));
+        assert!(approx(angle_delta(3.0, -3.0), 2.0 * PI - 6.0));
+        assert!(approx(angle_delta(-3.0, 3.0), 6.0 - 2.0 * PI));
+        assert!(approx(angle_delta(0.0, PI), PI));
+    }
+
+    #[test]
+    fn scale_factor_is_relative_to_start() {
+        assert!(approx(scale_factor(2.0, 3.0), 1.5));
+        assert!(approx(scale_factor(0.0, 3.0), 1.0));
+    }
+
+    #[test]
+    fn scale_factor_clamps_past_pivot() {
+        assert!(approx(scale_factor(1.0, 0.0), MIN_SCALE));
+        assert!(approx(scale_factor(1.0, -0.5), MIN_SCALE));
+        assert!(approx(scale_factor(-1.0, 2.0), MIN_SCALE));
+    }
+
+    #[test]
+    fn axis_rotation_takes_y_to_axis() {
+        for &axis in &[Axis::X, Axis::Y, Axis::Z] {
+            assert!(approx_vec(axis_rotation(axis) * Vec3::unit_y(), axis.unit()));
+        }
+    }
+}
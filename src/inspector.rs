@@ -0,0 +1,330 @@
+use bevy::prelude::*;
+use bevy::property::{DynamicProperties, Properties, Property, PropertyType};
+
+/// A line of the inspector, either a component, a nested map or sequence, or a field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InspectorRow {
+    pub depth: usize,
+    pub label: String,
+    /// Indices of the props leading to this row, starting from the component.
+    pub path: Vec<usize>,
+    /// The formatted value, or `None` for maps and sequences.
+    pub value: Option<String>,
+    /// Whether `parse_value` understands the value.
+    pub editable: bool,
+    /// Whether the value is a `bool`, which is toggled rather than typed.
+    pub toggle: bool,
+}
+
+/// Flattens a component into rows, one per nested map, sequence and field.
+pub fn inspect(component: &DynamicProperties) -> Vec<InspectorRow> {
+    let mut rows = vec![InspectorRow {
+        depth: 0,
+        label: component.type_name.clone(),
+        path: vec![],
+        value: None,
+        editable: false,
+        toggle: false,
+    }];
+    inspect_props(component, 1, &mut vec![], &mut rows);
+    rows
+}
+
+fn inspect_props(
+    props: &DynamicProperties,
+    depth: usize,
+    path: &mut Vec<usize>,
+    rows: &mut Vec<InspectorRow>,
+) {
+    for (index, prop) in props.props.iter().enumerate() {
+        let label = match props.property_type {
+            PropertyType::Seq => format!("[{}]", index),
+            _ => props
+                .prop_name(index)
+                .map(ToString::to_string)
+                .unwrap_or_else(|| index.to_string()),
+        };
+        path.push(index);
+        match prop.any().downcast_ref::<DynamicProperties>() {
+            Some(nested) => {
+                rows.push(InspectorRow {
+                    depth,
+                    label,
+                    path: path.clone(),
+                    value: None,
+                    editable: false,
+                    toggle: false,
+                });
+                inspect_props(nested, depth + 1, path, rows);
+            }
+            None => {
+                let value = format_value(&**prop);
+                rows.push(InspectorRow {
+                    depth,
+                    label,
+                    path: path.clone(),
+                    editable: value.is_some(),
+                    toggle: prop.any().is::<bool>(),
+                    value: Some(value.unwrap_or_else(|| format!("<{}>", prop.type_name()))),
+                });
+            }
+        }
+        path.pop();
+    }
+}
+
+/// Returns the prop at `path`, see `InspectorRow::path`.
+pub fn prop_at<'a>(component: &'a DynamicProperties, path: &[usize]) -> Option<&'a dyn Property> {
+    let (&last, parents) = path.split_last()?;
+    let mut props = component;
+    for &index in parents {
+        props = props
+            .prop_with_index(index)?
+            .any()
+            .downcast_ref::<DynamicProperties>()?;
+    }
+    props.prop_with_index(last)
+}
+
+/// Sets the prop at `path` to `value`, returning whether it was found.
+pub fn set_prop_at(component: &mut DynamicProperties, path: &[usize], value: &dyn Property) -> bool {
+    let (&last, parents) = match path.split_last() {
+        Some(split) => split,
+        None => return false,
+    };
+    let mut props = component;
+    for &index in parents {
+        props = match props
+            .prop_with_index_mut(index)
+            .and_then(|prop| prop.any_mut().downcast_mut::<DynamicProperties>())
+        {
+            Some(props) => props,
+            None => return false,
+        };
+    }
+    match props.prop_with_index_mut(last) {
+        Some(prop) => {
+            prop.set(value);
+            true
+        }
+        None => false,
+    }
+}
+
+fn format_floats(floats: &[f32]) -> String {
+    floats
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_floats(text: &str, len: usize) -> Option<Vec<f32>> {
+    let floats = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|part| !part.is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<f32>, _>>()
+        .ok()?;
+    if floats.len() == len {
+        Some(floats)
+    } else {
+        None
+    }
+}
+
+macro_rules! format_primitives {
+    ($any:expr, $($ty:ty),*) => {
+        $(
+            if let Some(value) = $any.downcast_ref::<$ty>() {
+                return Some(value.to_string());
+            }
+        )*
+    };
+}
+
+macro_rules! parse_primitives {
+    ($any:expr, $text:expr, $($ty:ty),*) => {
+        $(
+            if $any.is::<$ty>() {
+                return $text.trim().parse::<$ty>().ok().map(|value| Box::new(value) as Box<dyn Property>);
+            }
+        )*
+    };
+}
+
+/// Formats a value the inspector knows how to edit.
+///
+/// Colors and vectors are formatted as their components separated by spaces.
+pub fn format_value(prop: &dyn Property) -> Option<String> {
+    let any = prop.any();
+    format_primitives!(any, f32, f64, i8, i16, i32, i64, u8, u16, u32, u64, usize, isize, bool, String);
+    if let Some(color) = any.downcast_ref::<Color>() {
+        return Some(format_floats(&[color.r(), color.g(), color.b(), color.a()]));
+    }
+    if let Some(vec) = any.downcast_ref::<Vec2>() {
+        return Some(format_floats(&[vec.x(), vec.y()]));
+    }
+    if let Some(vec) = any.downcast_ref::<Vec3>() {
+        return Some(format_floats(&[vec.x(), vec.y(), vec.z()]));
+    }
+    if let Some(vec) = any.downcast_ref::<Vec4>() {
+        return Some(format_floats(&[vec.x(), vec.y(), vec.z(), vec.w()]));
+    }
+    if let Some(quat) = any.downcast_ref::<Quat>() {
+        return Some(format_floats(&[quat.x(), quat.y(), quat.z(), quat.w()]));
+    }
+    if let Some(mat) = any.downcast_ref::<Mat4>() {
+        return Some(format_floats(&mat.to_cols_array()));
+    }
+    None
+}
+
+/// Parses `text` into a value of the same type as `current`.
+pub fn parse_value(current: &dyn Property, text: &str) -> Option<Box<dyn Property>> {
+    let any = current.any();
+    parse_primitives!(any, text, f32, f64, i8, i16, i32, i64, u8, u16, u32, u64, usize, isize, bool);
+    if any.is::<String>() {
+        return Some(Box::new(text.to_string()));
+    }
+    if any.is::<Color>() {
+        let c = parse_floats(text, 4)?;
+        return Some(Box::new(Color::rgba(c[0], c[1], c[2], c[3])));
+    }
+    if any.is::<Vec2>() {
+        let v = parse_floats(text, 2)?;
+        return Some(Box::new(Vec2::new(v[0], v[1])));
+    }
+    if any.is::<Vec3>() {
+        let v = parse_floats(text, 3)?;
+        return Some(Box::new(Vec3::new(v[0], v[1], v[2])));
+    }
+    if any.is::<Vec4>() {
+        let v = parse_floats(text, 4)?;
+        return Some(Box::new(Vec4::new(v[0], v[1], v[2], v[3])));
+    }
+    if any.is::<Quat>() {
+        let q = parse_floats(text, 4)?;
+        return Some(Box::new(Quat::from_xyzw(q[0], q[1], q[2], q[3]).normalize()));
+    }
+    if any.is::<Mat4>() {
+        let m = parse_floats(text, 16)?;
+        let mut cols = [0.0; 16];
+        cols.copy_from_slice(&m);
+        return Some(Box::new(Mat4::from_cols_array(&cols)));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: Property + Clone>(value: T) -> T {
+        let text = format_value(&value).unwrap();
+        let parsed = parse_value(&value, &text).unwrap();
+        parsed.any().downcast_ref::<T>().unwrap().clone()
+    }
+
+    fn component() -> DynamicProperties {
+        let mut offset = DynamicProperties::map();
+        offset.set("translation", Vec3::new(1.0, 2.0, 3.0));
+        let mut component = DynamicProperties::map();
+        component.set("visible", true);
+        component.set("offset", offset);
+        component
+    }
+
+    #[test]
+    fn values_round_trip() {
+        assert_eq!(
+            round_trip(Vec3::new(1.5, -2.0, 0.25)),
+            Vec3::new(1.5, -2.0, 0.25)
+        );
+        assert_eq!(
+            round_trip(Color::rgba(0.25, 0.5, 1.0, 0.75)),
+            Color::rgba(0.25, 0.5, 1.0, 0.75)
+        );
+        assert!(round_trip(true));
+        assert_eq!(round_trip(3.5f32), 3.5);
+        assert_eq!(round_trip("bed".to_string()), "bed");
+
+        let quat = Quat::from_rotation_y(1.0);
+        let parsed = round_trip(quat);
+        assert!((parsed.dot(quat) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn floats_can_be_separated_by_commas() {
+        let parsed = parse_value(&Vec3::zero(), "1, 2,3").unwrap();
+        assert_eq!(
+            parsed.any().downcast_ref::<Vec3>(),
+            Some(&Vec3::new(1.0, 2.0, 3.0))
+        );
+    }
+
+    #[test]
+    fn wrong_values_are_rejected() {
+        assert!(parse_value(&Vec3::zero(), "1 2").is_none());
+        assert!(parse_value(&Vec3::zero(), "1 2 3 4").is_none());
+        assert!(parse_value(&Vec3::zero(), "1 x 3").is_none());
+        assert!(parse_value(&Color::WHITE, "1 1 1").is_none());
+        assert!(parse_value(&false, "maybe").is_none());
+        assert!(parse_value(&0u32, "-1").is_none());
+    }
+
+    #[test]
+    fn props_are_found_by_path() {
+        let component = component();
+        let visible = prop_at(&component, &[0]).unwrap();
+        assert_eq!(visible.any().downcast_ref::<bool>(), Some(&true));
+        let translation = prop_at(&component, &[1, 0]).unwrap();
+        assert_eq!(
+            translation.any().downcast_ref::<Vec3>(),
+            Some(&Vec3::new(1.0, 2.0, 3.0))
+        );
+    }
+
+    #[test]
+    fn props_are_set_by_path() {
+        let mut component = component();
+        assert!(set_prop_at(
+            &mut component,
+            &[1, 0],
+            &Vec3::new(4.0, 5.0, 6.0)
+        ));
+        let translation = prop_at(&component, &[1, 0]).unwrap();
+        assert_eq!(
+            translation.any().downcast_ref::<Vec3>(),
+            Some(&Vec3::new(4.0, 5.0, 6.0))
+        );
+    }
+
+    #[test]
+    fn bad_paths_are_rejected() {
+        let mut component = component();
+        assert!(prop_at(&component, &[]).is_none());
+        assert!(prop_at(&component, &[2]).is_none());
+        assert!(prop_at(&component, &[1, 1]).is_none());
+        assert!(prop_at(&component, &[0, 0]).is_none());
+        assert!(!set_prop_at(&mut component, &[], &false));
+        assert!(!set_prop_at(&mut component, &[2], &false));
+        assert!(!set_prop_at(&mut component, &[0, 0], &false));
+    }
+
+    #[test]
+    fn rows_follow_the_nesting() {
+        let rows = inspect(&component());
+        let labels = rows
+            .iter()
+            .map(|row| (row.depth, row.label.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels[1..],
+            [(1, "visible"), (1, "offset"), (2, "translation")]
+        );
+        assert!(rows[1].toggle);
+        assert_eq!(rows[3].path, vec![1, 0]);
+        assert_eq!(rows[3].value.as_deref(), Some("1 2 3"));
+    }
+}
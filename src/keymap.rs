@@ -0,0 +1,359 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::text_field::TextFocus;
+
+/// Where a binding is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KeyContext {
+    /// Always active.
+    Global,
+    /// Active while no text field has focus.
+    Viewport,
+    /// Active while a text field has focus.
+    TextField,
+}
+
+impl KeyContext {
+    fn overlaps(self, other: KeyContext) -> bool {
+        self == other || self == KeyContext::Global || other == KeyContext::Global
+    }
+
+    fn is_active(self, focus: &TextFocus) -> bool {
+        match self {
+            KeyContext::Global => true,
+            KeyContext::Viewport => !focus.0,
+            KeyContext::TextField => focus.0,
+        }
+    }
+}
+
+impl Default for KeyContext {
+    fn default() -> Self {
+        KeyContext::Viewport
+    }
+}
+
+/// A binding as written in the keymap file, e.g. `(key: "S", ctrl: true, context: Global)`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    /// Name of a `KeyCode` variant.
+    pub key: String,
+    #[serde(default)]
+    pub ctrl: bool,
+    #[serde(default)]
+    pub shift: bool,
+    #[serde(default)]
+    pub alt: bool,
+    #[serde(default)]
+    pub context: KeyContext,
+}
+
+impl KeyBinding {
+    pub fn new<S: Into<String>>(key: S, context: KeyContext) -> Self {
+        Self {
+            key: key.into(),
+            ctrl: false,
+            shift: false,
+            alt: false,
+            context,
+        }
+    }
+
+    pub fn ctrl(mut self) -> Self {
+        self.ctrl = true;
+        self
+    }
+
+    pub fn shift(mut self) -> Self {
+        self.shift = true;
+        self
+    }
+}
+
+/// A `KeyBinding` with its key resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Chord {
+    key: KeyCode,
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+    context: KeyContext,
+}
+
+impl Chord {
+    fn modifiers_match(&self, input: &Input<KeyCode>) -> bool {
+        // a chord whose key is a modifier doesn't also require that modifier
+        let held = |left, right| {
+            (input.pressed(left) && self.key != left) || (input.pressed(right) && self.key != right)
+        };
+        let ctrl = held(KeyCode::LControl, KeyCode::RControl);
+        let shift = held(KeyCode::LShift, KeyCode::RShift);
+        let alt = held(KeyCode::LAlt, KeyCode::RAlt);
+        self.ctrl == ctrl && self.shift == shift && self.alt == alt
+    }
+}
+
+#[derive(Debug)]
+pub enum KeymapError {
+    Io(PathBuf, io::Error),
+    Ron(PathBuf, ron::Error),
+    UnknownKey { action: String, key: String },
+    Conflict { key: String, first: String, second: String },
+}
+
+impl std::fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            KeymapError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            KeymapError::Ron(path, err) => write!(f, "{}: {}", path.display(), err),
+            KeymapError::UnknownKey { action, key } => {
+                write!(f, "unknown key {:?} bound to {}", key, action)
+            }
+            KeymapError::Conflict { key, first, second } => {
+                write!(f, "{} is bound to both {} and {}", key, first, second)
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeymapError {}
+
+/// Keymap actions that the editor's systems read directly, rather than through an
+/// `EditorAction` of the same id.
+pub const INPUT_ACTIONS: &[&str] = &[
+    "confirm",
+    "cancel",
+    "axis_x",
+    "axis_y",
+    "axis_z",
+    "plane_x",
+    "plane_y",
+    "plane_z",
+    "snap",
+    "field_confirm",
+    "field_cancel",
+    "field_up",
+    "field_down",
+];
+
+/// Named actions and the keys that trigger them.
+///
+/// Modifiers have to match exactly, so `S` doesn't fire while `Ctrl+S` is pressed.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(String, KeyBinding, Chord)>,
+}
+
+impl Keymap {
+    /// Builds a keymap, failing on unknown keys and on chords bound twice in overlapping
+    /// contexts.
+    pub fn new(actions: BTreeMap<String, Vec<KeyBinding>>) -> Result<Self, KeymapError> {
+        let mut bindings = Vec::<(String, KeyBinding, Chord)>::new();
+        for (action, action_bindings) in actions {
+            for binding in action_bindings {
+                let key = key_code(&binding.key).ok_or_else(|| KeymapError::UnknownKey {
+                    action: action.clone(),
+                    key: binding.key.clone(),
+                })?;
+                let chord = Chord {
+                    key,
+                    ctrl: binding.ctrl,
+                    shift: binding.shift,
+                    alt: binding.alt,
+                    context: binding.context,
+                };
+                if let Some((other, other_binding, _)) = bindings.iter().find(|(_, _, other)| {
+                    other.key == chord.key
+                        && other.ctrl == chord.ctrl
+                        && other.shift == chord.shift
+                        && other.alt == chord.alt
+                        && other.context.overlaps(chord.context)
+                }) {
+                    return Err(KeymapError::Conflict {
+                        key: describe(other_binding),
+                        first: other.clone(),
+                        second: action,
+                    });
+                }
+                bindings.push((action.clone(), binding, chord));
+            }
+        }
+        Ok(Self { bindings })
+    }
+
+    pub fn from_str(text: &str, path: &Path) -> Result<Self, KeymapError> {
+        let actions = ron::de::from_str(text).map_err(|err| KeymapError::Ron(path.to_path_buf(), err))?;
+        Self::new(actions)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, KeymapError> {
+        let text = fs::read_to_string(path).map_err(|err| KeymapError::Io(path.to_path_buf(), err))?;
+        Self::from_str(&text, path)
+    }
+
+    fn chords<'a>(
+        &'a self,
+        action: &'a str,
+        focus: &'a TextFocus,
+    ) -> impl Iterator<Item = &'a Chord> + 'a {
+        self.bindings
+            .iter()
+            .filter(move |(name, _, chord)| name == action && chord.context.is_active(focus))
+            .map(|(_, _, chord)| chord)
+    }
+
+    /// Whether `action` was triggered this frame.
+    pub fn just_pressed(&self, action: &str, input: &Input<KeyCode>, focus: &TextFocus) -> bool {
+        self.chords(action, focus)
+            .any(|chord| input.just_pressed(chord.key) && chord.modifiers_match(input))
+    }
+
+    /// Whether a key of `action` is held down.
+    pub fn pressed(&self, action: &str, input: &Input<KeyCode>, focus: &TextFocus) -> bool {
+        self.chords(action, focus)
+            .any(|chord| input.pressed(chord.key) && chord.modifiers_match(input))
+    }
+
+    /// Human readable keys of `action`, e.g. `Ctrl+S`.
+    pub fn describe(&self, action: &str) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|(name, _, _)| name == action)
+            .map(|(_, binding, _)| describe(binding))
+            .collect()
+    }
+
+    /// Bound actions that are neither `INPUT_ACTIONS` nor `known`, such as typos or
+    /// actions that were removed.
+    pub fn unknown_actions<F: Fn(&str) -> bool>(&self, known: F) -> Vec<String> {
+        self.actions()
+            .into_iter()
+            .map(|(action, _)| action)
+            .filter(|action| !INPUT_ACTIONS.contains(&action.as_str()) && !known(action))
+            .collect()
+    }
+
+    /// The bindings, grouped by action.
+    pub fn actions(&self) -> BTreeMap<String, Vec<KeyBinding>> {
+        let mut actions = BTreeMap::<String, Vec<KeyBinding>>::new();
+        for (action, binding, _) in &self.bindings {
+            actions.entry(action.clone()).or_default().push(binding.clone());
+        }
+        actions
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use KeyContext::*;
+
+        let mut actions = BTreeMap::new();
+        let mut bind = |action: &str, bindings: Vec<KeyBinding>| {
+            actions.insert(action.to_string(), bindings);
+        };
+        bind("save", vec![KeyBinding::new("S", Global).ctrl()]);
+        bind("save_as", vec![KeyBinding::new("S", Global).ctrl().shift()]);
+        bind("command_palette", vec![KeyBinding::new("P", Global).ctrl()]);
+        bind("undo", vec![KeyBinding::new("Z", Viewport).ctrl()]);
+        bind(
+            "redo",
+            vec![
+                KeyBinding::new("Y", Viewport).ctrl(),
+                KeyBinding::new("Z", Viewport).ctrl().shift(),
+            ],
+        );
+        bind("delete", vec![KeyBinding::new("Delete", Viewport)]);
+        bind("toggle_fly_camera", vec![KeyBinding::new("Q", Viewport)]);
+        bind("toggle_orbit_camera", vec![KeyBinding::new("O", Viewport)]);
+        bind("frame_selected", vec![KeyBinding::new("F", Viewport)]);
+        bind("frame_all", vec![KeyBinding::new("Home", Viewport)]);
+        bind("view_front", vec![KeyBinding::new("Numpad1", Viewport)]);
+        bind("view_side", vec![KeyBinding::new("Numpad3", Viewport)]);
+        bind("view_top", vec![KeyBinding::new("Numpad7", Viewport)]);
+        bind("toggle_orthographic", vec![KeyBinding::new("Numpad5", Viewport)]);
+        bind("translate", vec![KeyBinding::new("T", Viewport)]);
+        bind("rotate", vec![KeyBinding::new("R", Viewport)]);
+        bind("scale", vec![KeyBinding::new("S", Viewport)]);
+        bind("confirm", vec![KeyBinding::new("Return", Viewport)]);
+        bind("cancel", vec![KeyBinding::new("Escape", Viewport)]);
+        bind("axis_x", vec![KeyBinding::new("X", Viewport)]);
+        bind("axis_y", vec![KeyBinding::new("Y", Viewport)]);
+        bind("axis_z", vec![KeyBinding::new("Z", Viewport)]);
+        bind("plane_x", vec![KeyBinding::new("X", Viewport).shift()]);
+        bind("plane_y", vec![KeyBinding::new("Y", Viewport).shift()]);
+        bind("plane_z", vec![KeyBinding::new("Z", Viewport).shift()]);
+        bind("cycle_space", vec![KeyBinding::new("Tab", Viewport)]);
+        bind(
+            "snap",
+            vec![
+                KeyBinding::new("LControl", Viewport),
+                KeyBinding::new("RControl", Viewport),
+            ],
+        );
+        bind("field_confirm", vec![KeyBinding::new("Return", TextField)]);
+        bind("field_cancel", vec![KeyBinding::new("Escape", TextField)]);
+        bind("field_up", vec![KeyBinding::new("Up", TextField)]);
+        bind("field_down", vec![KeyBinding::new("Down", TextField)]);
+        Self::new(actions).expect("default keymap is invalid")
+    }
+}
+
+fn describe(binding: &KeyBinding) -> String {
+    let mut description = String::new();
+    if binding.ctrl {
+        description.push_str("Ctrl+");
+    }
+    if binding.shift {
+        description.push_str("Shift+");
+    }
+    if binding.alt {
+        description.push_str("Alt+");
+    }
+    description.push_str(&binding.key);
+    description
+}
+
+macro_rules! key_codes {
+    ($name:expr, $($key:ident),*) => {
+        match $name {
+            $(stringify!($key) => Some(KeyCode::$key),)*
+            _ => None,
+        }
+    };
+}
+
+/// Looks up a `KeyCode` by the name of its variant.
+pub fn key_code(name: &str) -> Option<KeyCode> {
+    key_codes!(
+        name, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0, A, B, C, D, E, F, G, H,
+        I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Escape, F1, F2, F3, F4, F5, F6, F7,
+        F8, F9, F10, F11, F12, Insert, Home, Delete, End, PageDown, PageUp, Left, Up, Right, Down,
+        Back, Return, Space, Tab, Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6,
+        Numpad7, Numpad8, Numpad9, NumpadEnter, Add, Subtract, Multiply, Divide, Decimal,
+        Apostrophe, Backslash, Comma, Equals, Grave, LBracket, Minus, Period, RBracket, Semicolon,
+        Slash, LControl, RControl, LShift, RShift, LAlt, RAlt
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_actions_skip_input_actions() {
+        use KeyContext::*;
+
+        let mut actions = BTreeMap::new();
+        actions.insert("confirm".to_string(), vec![KeyBinding::new("Return", Viewport)]);
+        actions.insert("save".to_string(), vec![KeyBinding::new("S", Global).ctrl()]);
+        actions.insert("digit_1".to_string(), vec![KeyBinding::new("Key1", Viewport)]);
+        let keymap = Keymap::new(actions).unwrap();
+        assert_eq!(keymap.unknown_actions(|id| id == "save"), vec!["digit_1".to_string()]);
+    }
+}
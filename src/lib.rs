@@ -1,10 +1,37 @@
+use std::fs;
 use std::marker::PhantomData;
+use std::path::Path;
 
+use bevy::asset::LoadState;
 use bevy::prelude::*;
+use bevy::property::DynamicProperties;
+use bevy::type_registry::TypeRegistry;
+use hashbrown::{HashMap, HashSet};
+use ron::Error as WriteError;
 
+use path::{resolve_asset_path, AssetRoot, PrefabOrigin};
+
+pub mod actions;
+pub mod camera;
 pub mod display;
 pub mod editor;
 pub mod entity;
+pub mod gizmo;
+pub mod inspector;
+pub mod keymap;
+pub mod manifest;
+pub mod material;
+pub mod numeric;
+pub mod outliner;
+pub mod path;
+pub mod plugin;
+pub mod search;
+pub mod settings;
+pub mod text_field;
+
+/// Name of a prefab entity, shown in the editor's outliner.
+#[derive(Debug, Default, Clone, Properties)]
+pub struct EntityName(pub String);
 
 #[derive(Debug, Properties)]
 pub struct Asset<T: Send + Sync + 'static> {
@@ -22,12 +49,24 @@ impl<T: Send + Sync + 'static> Asset<T> {
     }
 }
 
+impl<T: Send + Sync + 'static> Asset<T> {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
 impl<T: Send + Sync + 'static> Default for Asset<T> {
     fn default() -> Self {
         Self::new("")
     }
 }
 
+impl<T: Send + Sync + 'static> Clone for Asset<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.path.clone())
+    }
+}
+
 #[derive(Debug, Properties)]
 pub struct IntoAsset<T: Property + Send + Sync + 'static, U: Send + Sync + 'static> {
     t: T,
@@ -47,6 +86,12 @@ where
     }
 }
 
+impl<T: Property + Send + Sync + 'static, U: Send + Sync + 'static> IntoAsset<T, U> {
+    pub fn value(&self) -> &T {
+        &self.t
+    }
+}
+
 impl<T: Default + Property + Send + Sync + 'static, U: Send + Sync + 'static> Default
     for IntoAsset<T, U>
 where
@@ -77,32 +122,417 @@ impl<T: Default + Send + Sync + 'static> Default for DefaultComponent<T> {
     }
 }
 
+/// Replaced by `T::from_resources`, for components that need resources to be built.
+#[derive(Debug, Properties)]
+pub struct ResourceComponent<T: FromResources + Send + Sync + 'static> {
+    #[property(ignore)]
+    _phantom: PhantomData<T>,
+}
+
+impl<T: FromResources + Send + Sync + 'static> ResourceComponent<T> {
+    pub fn new() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: FromResources + Send + Sync + 'static> Default for ResourceComponent<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Replaced by `T::from((args, resources))`, for components that need both parameters from
+/// the prefab and resources to be built.
+#[derive(Debug, Properties)]
+pub struct ConstructedComponent<Args: Property + Send + Sync + 'static, T: Send + Sync + 'static> {
+    args: Args,
+    #[property(ignore)]
+    _phantom: PhantomData<T>,
+}
+
+impl<Args: Property + Send + Sync + 'static, T: Send + Sync + 'static> ConstructedComponent<Args, T>
+where
+    T: for<'a> From<(Args, &'a Resources)>,
+{
+    pub fn new(args: Args) -> Self {
+        Self {
+            args,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<Args: Default + Property + Send + Sync + 'static, T: Send + Sync + 'static> Default
+    for ConstructedComponent<Args, T>
+where
+    T: for<'a> From<(Args, &'a Resources)>,
+{
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+/// Remembers the path every `Handle<T>` created by `load_asset_system` was loaded from.
+///
+/// Entities whose asset failed to load share the `AssetPlaceholder` handle, so their paths
+/// are remembered per entity instead.
+#[derive(Debug)]
+pub struct AssetSources<T: Send + Sync + 'static> {
+    paths: HashMap<Handle<T>, String>,
+    failed: HashMap<Entity, String>,
+}
+
+impl<T: Send + Sync + 'static> AssetSources<T> {
+    pub fn get(&self, handle: &Handle<T>) -> Option<&str> {
+        self.paths.get(handle).map(String::as_str)
+    }
+
+    pub fn insert<S: Into<String>>(&mut self, handle: Handle<T>, path: S) {
+        self.paths.insert(handle, path.into());
+    }
+
+    pub fn remove(&mut self, handle: &Handle<T>) -> Option<String> {
+        self.paths.remove(handle)
+    }
+
+    /// The path of the asset `entity` failed to load, if it did.
+    pub fn failed(&self, entity: Entity) -> Option<&str> {
+        self.failed.get(&entity).map(String::as_str)
+    }
+
+    pub fn insert_failed<S: Into<String>>(&mut self, entity: Entity, path: S) {
+        self.failed.insert(entity, path.into());
+    }
+
+    pub fn remove_failed(&mut self, entity: Entity) -> Option<String> {
+        self.failed.remove(&entity)
+    }
+}
+
+impl<T: Send + Sync + 'static> Default for AssetSources<T> {
+    fn default() -> Self {
+        Self {
+            paths: HashMap::new(),
+            failed: HashMap::new(),
+        }
+    }
+}
+
+/// Remembers the value every `Handle<U>` created by `into_asset_system` was built from.
+#[derive(Debug)]
+pub struct IntoAssetSources<T: Send + Sync + 'static, U: Send + Sync + 'static> {
+    values: HashMap<Handle<U>, T>,
+}
+
+impl<T: Send + Sync + 'static, U: Send + Sync + 'static> IntoAssetSources<T, U> {
+    pub fn get(&self, handle: &Handle<U>) -> Option<&T> {
+        self.values.get(handle)
+    }
+
+    pub fn insert(&mut self, handle: Handle<U>, value: T) {
+        self.values.insert(handle, value);
+    }
+
+    pub fn remove(&mut self, handle: &Handle<U>) -> Option<T> {
+        self.values.remove(handle)
+    }
+
+    pub fn handles(&self) -> impl Iterator<Item = &Handle<U>> {
+        self.values.keys()
+    }
+}
+
+impl<T: Send + Sync + 'static, U: Send + Sync + 'static> Default for IntoAssetSources<T, U> {
+    fn default() -> Self {
+        Self {
+            values: HashMap::new(),
+        }
+    }
+}
+
+/// Turns a live component of `entity` back into its prefab representation.
+pub type RestoreFn = fn(&World, &Resources, Entity) -> Option<DynamicProperties>;
+
+/// The set of `RestoreFn`s `scene_from_world` runs on every entity.
+#[derive(Default)]
+pub struct AssetRestorers {
+    restorers: Vec<RestoreFn>,
+}
+
+impl AssetRestorers {
+    pub fn add(&mut self, restore: RestoreFn) -> &mut Self {
+        self.restorers.push(restore);
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RestoreFn> {
+        self.restorers.iter()
+    }
+}
+
+pub fn restore_asset<T: Send + Sync + 'static>(
+    world: &World,
+    resources: &Resources,
+    entity: Entity,
+) -> Option<DynamicProperties> {
+    let sources = resources.get::<AssetSources<T>>()?;
+    // a broken reference is saved as it was, so that it isn't lost
+    if let Some(path) = sources.failed(entity) {
+        return Some(Asset::<T>::new(path).to_dynamic());
+    }
+    let handle = world.get::<Handle<T>>(entity).ok()?;
+    let path = sources.get(&handle)?;
+    Some(Asset::<T>::new(path).to_dynamic())
+}
+
+pub fn restore_into_asset<T, U>(
+    world: &World,
+    resources: &Resources,
+    entity: Entity,
+) -> Option<DynamicProperties>
+where
+    T: Property + Clone + Send + Sync + 'static,
+    U: From<T> + Send + Sync + 'static,
+{
+    let handle = world.get::<Handle<U>>(entity).ok()?;
+    let sources = resources.get::<IntoAssetSources<T, U>>()?;
+    let value = sources.get(&handle)?;
+    Some(IntoAsset::<T, U>::new(value.clone()).to_dynamic())
+}
+
+/// Builds a prefab out of the live world, turning handles back into `Asset<T>` and
+/// `IntoAsset<T, U>` through the `AssetRestorers` resource.
+pub fn scene_from_world(world: &World, resources: &Resources) -> Scene {
+    let registry = resources.get::<TypeRegistry>().unwrap();
+    let restorers = resources.get::<AssetRestorers>().unwrap();
+    let mut scene = Scene::from_world(world, &registry.component.read());
+    for scene_entity in &mut scene.entities {
+        let entity = Entity::new(scene_entity.entity);
+        for restore in restorers.iter() {
+            if let Some(component) = restore(world, resources, entity) {
+                scene_entity.components.push(component);
+            }
+        }
+    }
+    scene
+}
+
+pub fn write_world_prefab<P: AsRef<Path>>(
+    path: P,
+    world: &World,
+    resources: &Resources,
+) -> Result<(), WriteError> {
+    let scene = scene_from_world(world, resources);
+    let registry = resources.get::<TypeRegistry>().unwrap();
+    let property = registry.property.read();
+    fs::write(path, scene.serialize_ron(&property)?)?;
+    Ok(())
+}
+
+/// Marks an entity whose `Asset<T>` could not be loaded.
+///
+/// The entity is left alive so the rest of the prefab keeps working.
+#[derive(Debug, Clone)]
+pub struct AssetLoadFailed {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct AssetLoadFailedEvent {
+    pub entity: Entity,
+    pub path: String,
+    pub error: String,
+}
+
+/// Handle substituted for an `Asset<T>` that failed to load, if any.
+#[derive(Debug)]
+pub struct AssetPlaceholder<T: Send + Sync + 'static> {
+    pub handle: Option<Handle<T>>,
+}
+
+impl<T: Send + Sync + 'static> AssetPlaceholder<T> {
+    pub fn new(handle: Handle<T>) -> Self {
+        Self {
+            handle: Some(handle),
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> Default for AssetPlaceholder<T> {
+    fn default() -> Self {
+        Self { handle: None }
+    }
+}
+
+/// Tracks an asset that has been requested but hasn't finished loading yet.
+#[derive(Debug)]
+pub struct AssetLoading<T: Send + Sync + 'static> {
+    path: String,
+    handle: Handle<T>,
+}
+
+fn asset_load_failed<T: Send + Sync + 'static>(
+    commands: &mut Commands,
+    events: &mut Events<AssetLoadFailedEvent>,
+    placeholder: &AssetPlaceholder<T>,
+    sources: &mut AssetSources<T>,
+    entity: Entity,
+    path: String,
+    error: String,
+) {
+    if let Some(handle) = placeholder.handle {
+        commands.insert_one(entity, handle);
+    }
+    sources.insert_failed(entity, path.clone());
+    report_load_failed(commands, events, entity, path, error);
+}
+
+pub(crate) fn report_load_failed(
+    commands: &mut Commands,
+    events: &mut Events<AssetLoadFailedEvent>,
+    entity: Entity,
+    path: String,
+    error: String,
+) {
+    commands.insert_one(
+        entity,
+        AssetLoadFailed {
+            path: path.clone(),
+            error: error.clone(),
+        },
+    );
+    events.send(AssetLoadFailedEvent {
+        entity,
+        path,
+        error,
+    });
+}
+
+/// Loads `Asset<T>` into a `Handle<T>`.
+///
+/// Paths are resolved with `path::resolve_asset_path`, against the entity's `PrefabOrigin`
+/// and the `AssetRoot`.
 pub fn load_asset_system<T: Send + Sync + 'static>(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mut query: Query<(Entity, &Asset<T>)>,
+    root: Res<AssetRoot>,
+    placeholder: Res<AssetPlaceholder<T>>,
+    mut sources: ResMut<AssetSources<T>>,
+    mut events: ResMut<Events<AssetLoadFailedEvent>>,
+    mut query: Query<(Entity, &Asset<T>, Option<&PrefabOrigin>)>,
+    mut loading: Query<(Entity, &AssetLoading<T>, Option<&AssetLoadFailed>)>,
 ) {
-    for (e, asset) in &mut query.iter() {
+    for (e, asset, origin) in &mut query.iter() {
         commands.remove_one::<Asset<T>>(e);
-        let handle = asset_server
-            .load::<T, _>(&asset.path)
-            .unwrap_or_else(|e| panic!("{}", e));
-        commands.insert_one(e, handle);
+        let path = resolve_asset_path(
+            &asset.path,
+            origin.map(|origin| origin.0.as_path()),
+            &root.0,
+        );
+        match asset_server.load::<T, _>(&path) {
+            Ok(handle) => {
+                sources.remove_failed(e);
+                sources.insert(handle, asset.path.clone());
+                commands.insert_one(e, handle);
+                commands.insert_one(
+                    e,
+                    AssetLoading {
+                        path: asset.path.clone(),
+                        handle,
+                    },
+                );
+            }
+            Err(err) => asset_load_failed(
+                &mut commands,
+                &mut events,
+                &placeholder,
+                &mut sources,
+                e,
+                asset.path.clone(),
+                err.to_string(),
+            ),
+        }
+    }
+
+    for (e, asset, failed) in &mut loading.iter() {
+        match asset_server.get_load_state(asset.handle) {
+            Some(LoadState::Loaded(_)) => {
+                commands.remove_one::<AssetLoading<T>>(e);
+                if failed.is_some() {
+                    commands.remove_one::<AssetLoadFailed>(e);
+                }
+            }
+            Some(LoadState::Failed(_)) => {
+                commands.remove_one::<AssetLoading<T>>(e);
+                asset_load_failed(
+                    &mut commands,
+                    &mut events,
+                    &placeholder,
+                    &mut sources,
+                    e,
+                    asset.path.clone(),
+                    format!("failed to load {}", asset.path),
+                );
+            }
+            _ => {}
+        }
     }
 }
 
+/// Turns `IntoAsset<T, U>` into a `Handle<U>`.
+///
+/// If the entity already owns a handle created by this system, the asset is updated in
+/// place instead, so that editing the value doesn't leak a new asset every time.  Assets
+/// no longer referenced by any entity are freed.
 pub fn into_asset_system<T: Property + Send + Sync + 'static, U: Send + Sync + 'static>(
     mut commands: Commands,
     mut assets: ResMut<Assets<U>>,
-    mut query: Query<(Entity, &IntoAsset<T, U>)>,
+    mut sources: ResMut<IntoAssetSources<T, U>>,
+    mut handles: Query<&Handle<U>>,
+    mut query: Query<(Entity, &IntoAsset<T, U>, Option<&Handle<U>>)>,
 ) where
     T: Clone,
     U: From<T>,
 {
-    for (e, asset) in &mut query.iter() {
+    free_orphaned_assets(&mut assets, &mut sources, &mut handles);
+
+    for (e, asset, handle) in &mut query.iter() {
         commands.remove_one::<IntoAsset<T, U>>(e);
-        let handle = assets.add(From::from(asset.t.clone()));
-        commands.insert_one(e, handle);
+        match handle {
+            Some(&handle) if sources.get(&handle).is_some() => {
+                assets.set(handle, From::from(asset.t.clone()));
+                sources.insert(handle, asset.t.clone());
+            }
+            _ => {
+                let handle = assets.add(From::from(asset.t.clone()));
+                sources.insert(handle, asset.t.clone());
+                commands.insert_one(e, handle);
+            }
+        }
+    }
+}
+
+/// Frees the assets in `sources` that no entity holds a handle to anymore.
+pub(crate) fn free_orphaned_assets<T: Send + Sync + 'static, U: Send + Sync + 'static>(
+    assets: &mut Assets<U>,
+    sources: &mut IntoAssetSources<T, U>,
+    handles: &mut Query<&Handle<U>>,
+) {
+    let mut live = HashSet::new();
+    for handle in &mut handles.iter() {
+        live.insert(*handle);
+    }
+    let orphans = sources
+        .handles()
+        .filter(|handle| !live.contains(handle))
+        .copied()
+        .collect::<Vec<_>>();
+    for handle in orphans {
+        sources.remove(&handle);
+        assets.remove(&handle);
     }
 }
 
@@ -115,3 +545,33 @@ pub fn default_component_system<T: Default + Send + Sync + 'static>(
         commands.insert_one(e, T::default());
     }
 }
+
+pub fn resource_component_system<T: FromResources + Send + Sync + 'static>(
+    world: &mut World,
+    resources: &mut Resources,
+) {
+    let entities = world
+        .query::<With<ResourceComponent<T>, Entity>>()
+        .iter()
+        .collect::<Vec<_>>();
+    for e in entities {
+        world.remove_one::<ResourceComponent<T>>(e).unwrap();
+        world.insert_one(e, T::from_resources(resources)).unwrap();
+    }
+}
+
+pub fn constructed_component_system<Args, T>(world: &mut World, resources: &mut Resources)
+where
+    Args: Property + Clone + Send + Sync + 'static,
+    T: for<'a> From<(Args, &'a Resources)> + Send + Sync + 'static,
+{
+    let constructed = world
+        .query::<(Entity, &ConstructedComponent<Args, T>)>()
+        .iter()
+        .map(|(e, component)| (e, component.args.clone()))
+        .collect::<Vec<_>>();
+    for (e, args) in constructed {
+        world.remove_one::<ConstructedComponent<Args, T>>(e).unwrap();
+        world.insert_one(e, T::from((args, &*resources))).unwrap();
+    }
+}
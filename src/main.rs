@@ -1,24 +1,56 @@
+use std::cmp::Ordering;
+use std::env;
 use std::fs::{self, File};
-use std::mem;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::SystemTime;
 
 use bevy::asset::AssetServerError;
 use bevy::ecs::IntoThreadLocalSystem;
 use bevy::input::mouse::*;
 use bevy::prelude::*;
-use bevy::property::{erased_serde, DeserializeProperty, DynamicProperties};
+use bevy::property::{erased_serde, DeserializeProperty, DynamicProperties, PropertyTypeRegistry};
+use bevy::render::camera::CameraProjection;
 use bevy::scene;
 use bevy::type_registry::*;
+use bevy::window::ReceivedCharacter;
 use bevy_fly_camera::*;
 use bevy_mod_picking::*;
+use clipboard::{ClipboardContext, ClipboardProvider};
 use hashbrown::HashMap;
 use ron::Error as WriteError;
 
-use bevy_prefab_editor::display::*;
+use bevy_prefab_editor::actions::*;
+use bevy_prefab_editor::camera::*;
 use bevy_prefab_editor::editor::*;
+// shadows `bevy::prelude::Axis`
+use bevy_prefab_editor::editor::Axis;
 use bevy_prefab_editor::entity::*;
+use bevy_prefab_editor::gizmo::*;
+use bevy_prefab_editor::inspector::*;
+use bevy_prefab_editor::keymap::*;
+use bevy_prefab_editor::manifest::*;
+use bevy_prefab_editor::outliner::*;
+use bevy_prefab_editor::path::*;
+use bevy_prefab_editor::plugin::*;
+use bevy_prefab_editor::settings::*;
+use bevy_prefab_editor::text_field::*;
 use bevy_prefab_editor::*;
 
+pub const BUNDLES_PATH: &str = "assets/editor_bundles.ron";
+pub const PROPERTIES_PATH: &str = "assets/editor_properties.ron";
+pub const KEYMAP_PATH: &str = "assets/editor_keymap.ron";
+pub const SETTINGS_PATH: &str = "assets/editor_settings.ron";
+
+/// How long the camera takes to reach what it's framing, in seconds.
+pub const CAMERA_TWEEN_SECONDS: f32 = 0.3;
+/// Radians the orbit camera turns per pixel of mouse motion.
+pub const ORBIT_SENSITIVITY: f32 = 0.005;
+/// Smallest radius framed, so that single points don't bring the camera into them.
+pub const MIN_FRAME_RADIUS: f32 = 0.5;
+/// How far orthographic views see in front of and behind the camera.
+pub const ORTHO_DEPTH: f32 = 1000.0;
+
 pub const BUTTON_NONE_MATERIAL: Handle<ColorMaterial> = Handle::from_bytes([
     250, 189, 108, 221, 189, 142, 172, 126, 18, 121, 71, 114, 210, 186, 138, 64,
 ]);
@@ -38,31 +70,322 @@ pub const BUTTON_TOGGLED_MATERIAL: Handle<ColorMaterial> = Handle::from_bytes([
 #[derive(Default, Debug, Clone, Copy)]
 pub struct ButtonToggled(bool);
 
+pub struct EditorFont(Handle<Font>);
+
+/// UI node listing the assets the open prefab depends on.
+pub struct DependencyPanel;
+
+/// Text at the bottom of the window describing the `EditorMode`.
+pub struct StatusBar;
+
+/// Root of the gizmo meshes, which follows the selection.
+pub struct Gizmo;
+
+/// A gizmo handle being dragged.
+#[derive(Debug, Clone, Copy)]
+struct GizmoDrag {
+    handle: GizmoHandle,
+    /// The pivot when the drag started.
+    origin: Vec3,
+    /// The handle's axis in world space.
+    axis: Vec3,
+    /// Position along the handle's axis, or angle around it, when the drag started.
+    start: f32,
+    last: f32,
+}
+
+#[derive(Debug, Default)]
+pub struct GizmoState {
+    drag: Option<GizmoDrag>,
+}
+
+/// Transforms of the selection from before the current transform, which is only written
+/// to the scene when it's confirmed, so that it can be cancelled.
+#[derive(Debug, Default)]
+pub struct TransformSession {
+    originals: Option<Vec<(Entity, Transform)>>,
+}
+
+/// State of the editor camera on top of its `FlyCamera`.
+#[derive(Debug)]
+pub struct EditorCamera {
+    /// Whether the camera circles `orbit` with the middle mouse button instead of flying.
+    orbiting: bool,
+    /// Kept up to date by the frame actions, even when not orbiting.
+    orbit: Orbit,
+    tween: Option<CameraTween>,
+    /// Whether the camera's `PerspectiveProjection` is replaced by an orthographic one.
+    orthographic: bool,
+    /// Half the height of the world seen in orthographic views, zoomed with the wheel.
+    ortho_height: f32,
+}
+
+impl Default for EditorCamera {
+    fn default() -> Self {
+        Self {
+            orbiting: false,
+            orbit: Orbit::default(),
+            tween: None,
+            orthographic: false,
+            ortho_height: 5.0,
+        }
+    }
+}
+
+/// A smooth move of the camera, as translation and rotation.
+#[derive(Debug, Clone, Copy)]
+struct CameraTween {
+    from: (Vec3, Quat),
+    to: (Vec3, Quat),
+    elapsed: f32,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum ButtonFunction {
     Save,
     AddComponent,
+    EditProperty,
+    SpawnBundle,
+    CommandPalette,
+}
+
+/// UI node listing the components of the selected entity.
+pub struct InspectorPanel;
+
+/// UI node listing the suggestions for the "Add component" field.
+pub struct AutocompletePanel;
+
+/// Number of suggestions shown by the autocomplete panel.
+const AUTOCOMPLETE_LEN: usize = 8;
+
+/// Suggestions for the "Add component" field, from `DefaultProperties`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Autocomplete {
+    matches: Vec<String>,
+    cursor: usize,
+    error: Option<String>,
+}
+
+impl Autocomplete {
+    /// Shows the best `names`, keeping the cursor if the suggestions didn't change.
+    fn update(&mut self, names: Vec<&str>) {
+        let matches = names
+            .into_iter()
+            .take(AUTOCOMPLETE_LEN)
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        if matches != self.matches {
+            self.matches = matches;
+            self.cursor = 0;
+        }
+    }
+
+    fn up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    fn down(&mut self) {
+        if self.cursor + 1 < self.matches.len() {
+            self.cursor += 1;
+        }
+    }
+
+    fn selected(&self) -> Option<&str> {
+        self.matches.get(self.cursor).map(String::as_str)
+    }
+
+    fn clear(&mut self) {
+        self.matches.clear();
+        self.cursor = 0;
+    }
+}
+
+/// Floating UI node of the command palette, hidden while it's closed.
+pub struct CommandPaletteRoot;
+
+/// UI node listing the actions matching the command palette's text.
+pub struct CommandPaletteList;
+
+/// State of the command palette, which runs `EditorActions` by name.
+#[derive(Debug, Default)]
+pub struct CommandPalette {
+    list: Autocomplete,
+    ids: Vec<String>,
+    open: bool,
+    requested: bool,
+    /// The palette is asking for the path to save the prefab as instead of an action.
+    save_as: bool,
+    /// Path entered for "Save prefab as", until `save_system` writes it.
+    save_path: Option<PathBuf>,
+}
+
+impl CommandPalette {
+    fn update(&mut self, actions: Vec<&EditorAction>, keymap: &Keymap) {
+        let labels = actions
+            .iter()
+            .take(AUTOCOMPLETE_LEN)
+            .map(|action| {
+                let keys = keymap.describe(&action.id);
+                if keys.is_empty() {
+                    action.name.clone()
+                } else {
+                    format!("{}    {}", action.name, keys.join(", "))
+                }
+            })
+            .collect::<Vec<_>>();
+        self.ids = actions
+            .iter()
+            .take(AUTOCOMPLETE_LEN)
+            .map(|action| action.id.clone())
+            .collect();
+        self.list.update(labels.iter().map(String::as_str).collect());
+    }
+
+    fn selected(&self) -> Option<&str> {
+        self.ids.get(self.list.cursor).map(String::as_str)
+    }
+}
+
+/// UI node listing the `DefaultBundles`.
+pub struct BundlePalette;
+
+/// Palette button spawning the named bundle.
+#[derive(Debug, Clone)]
+pub struct PaletteEntry(String);
+
+/// Distance in front of the camera at which bundles are spawned.
+const SPAWN_DISTANCE: f32 = 5.0;
+
+/// UI node listing the entities of the prefab.
+pub struct OutlinerPanel;
+
+/// Outliner button selecting the scene entity with the given id.
+#[derive(Debug, Clone, Copy)]
+pub struct OutlinerEntry(u32);
+
+/// A field of a scene entity's component, edited by an inspector button.
+#[derive(Debug, Clone)]
+pub struct InspectorField {
+    entity: u32,
+    component: usize,
+    path: Vec<usize>,
+    label: String,
+    value: String,
+}
+
+impl InspectorField {
+    /// Sets the field to `text` through `EditorCommands`, returning whether `text` was valid.
+    fn write(&self, text: &str, scene: &Scene, commands: &mut EditorCommands) -> bool {
+        let component = match scene
+            .entities
+            .get(self.entity as usize)
+            .and_then(|scene_entity| scene_entity.components.get(self.component))
+        {
+            Some(component) => component,
+            None => return false,
+        };
+        let value = match prop_at(component, &self.path).and_then(|prop| parse_value(prop, text)) {
+            Some(value) => value,
+            None => return false,
+        };
+        let mut component = component.clone_prop().as_properties().unwrap().to_dynamic();
+        if !set_prop_at(&mut component, &self.path, &*value) {
+            return false;
+        }
+        let name = component.type_name.clone();
+        commands.insert_one(self.entity, component);
+        commands.sync_one_to_world(self.entity, name);
+        true
+    }
 }
 
 pub type EditorCommand = Box<dyn FnOnce(&mut World, &Resources) + Send + Sync + 'static>;
 
+/// Why the open prefab couldn't be read or reloaded.
+#[derive(Debug)]
+pub enum ReloadError {
+    Asset(AssetServerError),
+    /// The prefab uses a component type that isn't registered.
+    UnknownComponent(String),
+}
+
+impl std::fmt::Display for ReloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ReloadError::Asset(err) => write!(f, "{}", err),
+            ReloadError::UnknownComponent(name) => write!(f, "unknown component {}", name),
+        }
+    }
+}
+
+impl std::error::Error for ReloadError {}
+
+impl From<AssetServerError> for ReloadError {
+    fn from(err: AssetServerError) -> Self {
+        ReloadError::Asset(err)
+    }
+}
+
+/// The first component of `scene` whose type isn't registered, if any.
+fn unknown_component(scene: &Scene, registry: &ComponentRegistry) -> Option<String> {
+    scene
+        .entities
+        .iter()
+        .flat_map(|scene_entity| &scene_entity.components)
+        .find(|component| registry.get_with_name(&component.type_name).is_none())
+        .map(|component| component.type_name.clone())
+}
+
 pub struct Editor {
     entity_map: HashMap<u32, Entity>,
     next_entity: u32,
     current_entity: Option<usize>,
     scene: Handle<Scene>,
+    path: PathBuf,
+    dirty: bool,
+    /// A problem with the prefab on disk, shown in the status bar until it's next saved or
+    /// reloaded.
+    notice: Option<String>,
 }
 
 impl Editor {
+    /// Writes the prefab to `path`.
+    ///
+    /// If `path` is in a different directory than the open prefab, prefab-relative asset
+    /// paths are rewritten so they keep pointing at the same files. The open scene only
+    /// takes the rewritten paths once the file is written.
     pub fn write<P: AsRef<Path>>(
-        &self,
+        &mut self,
         path: P,
         registry: &TypeRegistry,
-        assets: &Assets<Scene>,
+        assets: &mut Assets<Scene>,
     ) -> Result<(), WriteError> {
+        let path = path.as_ref();
+        let from = prefab_dir(&self.path).to_path_buf();
+        let to = prefab_dir(path);
         let scene = assets.get(&self.scene).unwrap();
+        let relocated = if normalize(&from) != normalize(to) {
+            let mut relocated = clone_scene(scene);
+            for scene_entity in &mut relocated.entities {
+                for component in &mut scene_entity.components {
+                    for_each_asset_path_mut(component, &mut |_, asset_path| {
+                        *asset_path = relocate_asset_path(asset_path, &from, to);
+                    });
+                }
+            }
+            Some(relocated)
+        } else {
+            None
+        };
         let property = registry.property.read();
-        fs::write(path, scene.serialize_ron(&property)?)?;
+        let ron = relocated.as_ref().unwrap_or(scene).serialize_ron(&property)?;
+        fs::write(path, ron)?;
+        if let Some(relocated) = relocated {
+            *assets.get_mut(&self.scene).unwrap() = relocated;
+        }
+        self.path = path.to_path_buf();
+        self.dirty = false;
+        self.notice = None;
         Ok(())
     }
 
@@ -71,48 +394,162 @@ impl Editor {
         path: P,
         world: &mut World,
         resources: &Resources,
-    ) -> Result<(), AssetServerError> {
+    ) -> Result<(), ReloadError> {
         let asset_server = resources.get::<AssetServer>().unwrap();
         let mut assets = resources.get_mut::<Assets<Scene>>().unwrap();
         let registry = resources.get::<TypeRegistry>().unwrap();
-        let handle = asset_server.load_sync(&mut assets, path)?;
-        let scene = assets.get(&handle).unwrap();
+        self.path = path.as_ref().to_path_buf();
+        let handle = asset_server.load_sync(&mut assets, &path)?;
 
         let component_registry = registry.component.read();
+        if let Some(name) = unknown_component(assets.get(&handle).unwrap(), &component_registry) {
+            assets.remove(&handle);
+            return Err(ReloadError::UnknownComponent(name));
+        }
+        let scene = assets.get(&handle).unwrap();
 
         self.next_entity = 0;
         for scene_entity in &scene.entities {
-            let entity = world.spawn(WidgetComponents::new(self.next_entity));
+            let entity = self.spawn_widget(world);
+            for component in &scene_entity.components {
+                let registration = component_registry
+                    .get_with_name(&component.type_name)
+                    .unwrap();
+                registration.add_component_to_entity(world, resources, entity, component);
+            }
+        }
+
+        // every load adds a new scene asset
+        assets.remove(&self.scene);
+        self.scene = handle;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Reads the open prefab again and updates the existing widgets to match it.
+    ///
+    /// Widgets are kept where possible, so that the selection survives the reload.  Widgets
+    /// that lost a component are respawned, because components can't be removed by name.
+    /// If the prefab can't be read, the world and the old scene are left alone.
+    pub fn reload(&mut self, world: &mut World, resources: &Resources) -> Result<(), ReloadError> {
+        let asset_server = resources.get::<AssetServer>().unwrap();
+        let mut assets = resources.get_mut::<Assets<Scene>>().unwrap();
+        let registry = resources.get::<TypeRegistry>().unwrap();
+
+        let old_components = assets
+            .get(&self.scene)
+            .map(|scene| {
+                scene
+                    .entities
+                    .iter()
+                    .map(|scene_entity| {
+                        scene_entity
+                            .components
+                            .iter()
+                            .map(|component| component.type_name.clone())
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let handle = asset_server.load_sync(&mut assets, &self.path)?;
+        let component_registry = registry.component.read();
+        if let Some(name) = unknown_component(assets.get(&handle).unwrap(), &component_registry) {
+            assets.remove(&handle);
+            return Err(ReloadError::UnknownComponent(name));
+        }
+        let scene = assets.get(&handle).unwrap();
+
+        let old_len = self.next_entity;
+        self.next_entity = 0;
+        for (index, scene_entity) in scene.entities.iter().enumerate() {
+            let id = index as u32;
+            let kept = old_components.get(index).map_or(false, |old| {
+                old.iter().all(|name| {
+                    scene_entity
+                        .components
+                        .iter()
+                        .any(|component| &component.type_name == name)
+                })
+            });
+            let existing = self.entity_map.get(&id).copied();
+            let entity = match existing {
+                Some(entity) if kept => {
+                    self.next_entity += 1;
+                    entity
+                }
+                Some(entity) => {
+                    world.despawn(entity).unwrap();
+                    self.spawn_widget(world)
+                }
+                None => self.spawn_widget(world),
+            };
             for component in &scene_entity.components {
                 let registration = component_registry
                     .get_with_name(&component.type_name)
                     .unwrap();
                 registration.add_component_to_entity(world, resources, entity, component);
             }
-            let next_entity = self.next_entity;
-            self.entity_map.insert(next_entity, entity);
         }
 
+        for id in self.next_entity..old_len {
+            if let Some(entity) = self.entity_map.remove(&id) {
+                world.despawn(entity).unwrap();
+            }
+        }
+
+        // every load adds a new scene asset
+        assets.remove(&self.scene);
         self.scene = handle;
+        self.dirty = false;
+        self.notice = None;
+        // the history's ids refer to the old scene
+        resources.get_mut::<EditorHistory>().unwrap().clear();
         Ok(())
     }
+
+    fn spawn_widget(&mut self, world: &mut World) -> Entity {
+        let entity = world.spawn(WidgetComponents::new(self.next_entity));
+        world
+            .insert_one(entity, PrefabOrigin::of(&self.path))
+            .unwrap();
+        self.entity_map.insert(self.next_entity, entity);
+        self.next_entity += 1;
+        entity
+    }
 }
 
 #[derive(Default)]
 pub struct EditorCommands {
-    next_entity: u32,
-    current_entity: Option<u32>,
     queue: Vec<EditorCommand>,
 }
 
 impl EditorCommands {
     pub fn apply(&mut self, world: &mut World, resources: &Resources) {
-        self.current_entity = None;
         for command in self.queue.drain(..) {
             command(world, resources);
         }
     }
 
+    /// Calls `f` with the id of the entity last spawned through these commands, once it's
+    /// spawned.
+    ///
+    /// The id isn't known before, since despawns and reloads queued ahead of the spawn
+    /// renumber the entities.
+    pub fn with_spawned<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnOnce(u32, &mut World, &Resources) + Send + Sync + 'static,
+    {
+        self.queue.push(Box::new(move |world, resources| {
+            let spawned = resources.get::<Editor>().unwrap().current_entity;
+            if let Some(index) = spawned {
+                f(index as u32, world, resources);
+            }
+        }));
+        self
+    }
+
     pub fn spawn(&mut self, components: EditorBundle) -> &mut Self {
         self.queue.push(Box::new(move |world, resources| {
             let mut editor = resources.get_mut::<Editor>().unwrap();
@@ -125,7 +562,8 @@ impl EditorCommands {
 
             let components = components.into_inner();
 
-            let entity = world.spawn(WidgetComponents::new(editor.next_entity));
+            let id = editor.next_entity;
+            let entity = editor.spawn_widget(world);
             for component in &components {
                 let registration = component_registry
                     .get_with_name(&component.type_name)
@@ -133,22 +571,18 @@ impl EditorCommands {
                 registration.add_component_to_entity(world, resources, entity, component);
             }
 
-            let next_entity = editor.next_entity;
-            editor.entity_map.insert(next_entity, entity);
             scene.entities.push(scene::Entity {
-                entity: editor.next_entity,
+                entity: id,
                 components,
             });
-            editor.next_entity += 1;
+            editor.dirty = true;
         }));
-        self.current_entity = Some(self.next_entity);
-        self.next_entity += 1;
         self
     }
 
     pub fn with(&mut self, component: DynamicProperties) -> &mut Self {
         self.queue.push(Box::new(move |_world, resources| {
-            let editor = resources.get::<Editor>().unwrap();
+            let mut editor = resources.get_mut::<Editor>().unwrap();
             let mut assets = resources.get_mut::<Assets<Scene>>().unwrap();
             let registry = resources.get::<TypeRegistry>().unwrap();
             let component_registry = registry.component.read();
@@ -158,13 +592,14 @@ impl EditorCommands {
             scene.entities[current_entity]
                 .components
                 .add(component, &component_registry);
+            editor.dirty = true;
         }));
         self
     }
 
     pub fn with_bundle(&mut self, bundle: EditorBundle) -> &mut Self {
         self.queue.push(Box::new(move |_world, resources| {
-            let editor = resources.get::<Editor>().unwrap();
+            let mut editor = resources.get_mut::<Editor>().unwrap();
             let mut assets = resources.get_mut::<Assets<Scene>>().unwrap();
             let registry = resources.get::<TypeRegistry>().unwrap();
             let component_registry = registry.component.read();
@@ -174,13 +609,14 @@ impl EditorCommands {
             scene.entities[current_entity]
                 .components
                 .add_bundle(bundle.into_inner(), &component_registry);
+            editor.dirty = true;
         }));
         self
     }
 
     pub fn insert_one(&mut self, entity: u32, component: DynamicProperties) -> &mut Self {
         self.queue.push(Box::new(move |_world, resources| {
-            let editor = resources.get::<Editor>().unwrap();
+            let mut editor = resources.get_mut::<Editor>().unwrap();
             let mut assets = resources.get_mut::<Assets<Scene>>().unwrap();
             let registry = resources.get::<TypeRegistry>().unwrap();
             let component_registry = registry.component.read();
@@ -189,6 +625,47 @@ impl EditorCommands {
             scene.entities[entity as usize]
                 .components
                 .add(component, &component_registry);
+            editor.dirty = true;
+        }));
+        self
+    }
+
+    /// Removes an entity and its children from the prefab.
+    ///
+    /// The ids of the remaining entities are shifted down, so that they stay contiguous.
+    pub fn despawn(&mut self, entity: u32) -> &mut Self {
+        self.queue.push(Box::new(move |world, resources| {
+            let mut editor = resources.get_mut::<Editor>().unwrap();
+            let mut assets = resources.get_mut::<Assets<Scene>>().unwrap();
+
+            let scene = assets.get_mut(&editor.scene).unwrap();
+            let removed = scene_subtree(scene, entity);
+            let remap = |id: u32| id - removed.iter().filter(|&&other| other < id).count() as u32;
+
+            scene
+                .entities
+                .retain(|scene_entity| !removed.contains(&scene_entity.entity));
+            for scene_entity in &mut scene.entities {
+                scene_entity.entity = remap(scene_entity.entity);
+                if let Some(parent) = scene_parent(&scene_entity.components) {
+                    set_scene_parent(&mut scene_entity.components, remap(parent));
+                }
+            }
+
+            let mut entity_map = HashMap::new();
+            for (id, world_entity) in editor.entity_map.drain() {
+                if removed.contains(&id) {
+                    world.despawn(world_entity).unwrap();
+                } else {
+                    let id = remap(id);
+                    world.get_mut::<Widget>(world_entity).unwrap().0 = id;
+                    entity_map.insert(id, world_entity);
+                }
+            }
+            editor.entity_map = entity_map;
+            editor.next_entity -= removed.len() as u32;
+            editor.current_entity = None;
+            editor.dirty = true;
         }));
         self
     }
@@ -238,6 +715,88 @@ impl EditorCommands {
     }
 }
 
+/// Selects the widget `entity`, deselecting every other widget.
+fn select_only(world: &mut World, editor: &Editor, entity: u32) {
+    for (&id, &world_entity) in &editor.entity_map {
+        let mut selected = world.get_mut::<Selected>(world_entity).unwrap();
+        if selected.0 != (id == entity) {
+            selected.0 = id == entity;
+        }
+    }
+}
+
+/// Something done in the editor that can be undone.
+#[derive(Debug, Clone)]
+pub enum HistoryEntry {
+    Spawn { entity: u32, bundle: EditorBundle },
+}
+
+/// Undo and redo stacks of the editor.
+#[derive(Debug, Default)]
+pub struct EditorHistory {
+    undo: Vec<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+}
+
+impl EditorHistory {
+    pub fn push(&mut self, entry: HistoryEntry) {
+        self.undo.push(entry);
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self, commands: &mut EditorCommands) {
+        if let Some(entry) = self.undo.pop() {
+            match &entry {
+                HistoryEntry::Spawn { entity, .. } => {
+                    commands.despawn(*entity);
+                }
+            }
+            self.redo.push(entry);
+        }
+    }
+
+    /// Redoes the last undone entry, which is back on the undo stack once its commands ran.
+    pub fn redo(&mut self, commands: &mut EditorCommands) {
+        if let Some(entry) = self.redo.pop() {
+            match entry {
+                HistoryEntry::Spawn { bundle, .. } => {
+                    commands
+                        .spawn(bundle.clone())
+                        .with_spawned(move |entity, world, resources| {
+                            select_only(world, &resources.get::<Editor>().unwrap(), entity);
+                            let mut history = resources.get_mut::<EditorHistory>().unwrap();
+                            history.undo.push(HistoryEntry::Spawn { entity, bundle });
+                        });
+                }
+            }
+        }
+    }
+
+    /// Forgets every entry, whose ids don't match the entities anymore.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Updates the entries after `entity` was despawned outside of the history.
+    pub fn despawned(&mut self, entity: u32) {
+        for entries in &mut [&mut self.undo, &mut self.redo] {
+            entries.retain(|entry| match entry {
+                HistoryEntry::Spawn { entity: spawned, .. } => *spawned != entity,
+            });
+            for entry in entries.iter_mut() {
+                match entry {
+                    HistoryEntry::Spawn { entity: spawned, .. } => {
+                        if *spawned > entity {
+                            *spawned -= 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 impl FromResources for Editor {
     fn from_resources(resources: &Resources) -> Self {
         let mut assets = resources.get_mut::<Assets<Scene>>().unwrap();
@@ -247,6 +806,46 @@ impl FromResources for Editor {
             next_entity: 0,
             current_entity: None,
             scene,
+            path: "assets/prefab.scn".into(),
+            dirty: false,
+            notice: None,
+        }
+    }
+}
+
+/// Polls the open prefab and the library files for changes made outside of the editor.
+pub struct FileWatcher {
+    timer: Timer,
+    modified: HashMap<PathBuf, SystemTime>,
+}
+
+impl FileWatcher {
+    /// Returns whether `path` changed since it was last checked or touched.
+    pub fn changed(&mut self, path: &Path) -> bool {
+        let modified = match fs::metadata(path).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => modified,
+            Err(_) => return false,
+        };
+        match self.modified.insert(path.to_path_buf(), modified) {
+            Some(previous) => previous != modified,
+            None => false,
+        }
+    }
+
+    /// Records the current state of `path`, so that the editor's own writes aren't
+    /// mistaken for outside changes.
+    pub fn touch(&mut self, path: &Path) {
+        if let Ok(modified) = fs::metadata(path).and_then(|metadata| metadata.modified()) {
+            self.modified.insert(path.to_path_buf(), modified);
+        }
+    }
+}
+
+impl Default for FileWatcher {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(0.5, true),
+            modified: HashMap::new(),
         }
     }
 }
@@ -257,54 +856,149 @@ fn main() {
         .add_default_plugins()
         .add_plugin(FlyCameraPlugin)
         .add_plugin(PickingPlugin)
-        .register_component::<Asset<Mesh>>()
-        .register_component::<IntoAsset<Color, StandardMaterial>>()
-        .register_component::<DefaultComponent<GlobalTransform>>()
+        .add_plugin(
+            PrefabPlugin::new()
+                .asset::<Mesh>()
+                .into_asset::<Color, StandardMaterial>()
+                .material()
+                .default_component::<GlobalTransform>(),
+        );
+
+    if env::args().nth(1).as_deref() == Some("--deps") {
+        dependency_report(builder.resources(), env::args().nth(2));
+        return;
+    }
+
+    add_editor_actions(&mut builder);
+    builder
         .add_event::<EditorEvent>()
         .init_resource::<Editor>()
         .init_resource::<EditorCommands>()
         .init_resource::<EditorMode>()
         .init_resource::<DefaultBundles>()
-        .init_resource::<DefaultProperties>()
+        .init_resource::<FileWatcher>()
+        .init_resource::<Autocomplete>()
+        .init_resource::<EditorHistory>()
+        .init_resource::<TextFocus>()
+        .init_resource::<GizmoState>()
+        .init_resource::<TransformSession>()
+        .init_resource::<CommandPalette>()
+        .init_resource::<EditorCamera>()
         .add_startup_system(setup.system())
         .add_startup_system(setup_thread_local.thread_local_system())
+        .add_startup_system(setup_gizmo.system())
         .add_system(save_system.system())
-        .add_system(camera_system.system())
         .add_system(button_enter_system.system())
         .add_system(button_system.system())
-        .add_system(text_button_system.system());
+        .add_system(text_field_system.system())
+        .add_system(text_button_system.system())
+        .add_system(autocomplete_system.system())
+        .add_system(inspector_system.system())
+        .add_system(inspector_toggle_system.system())
+        .add_system(outliner_system.system())
+        .add_system(outliner_click_system.system())
+        .add_system(library_actions_system.system())
+        .add_system(action_shortcut_system.system())
+        // after `library_actions_system`, which registers the library's actions
+        .add_system(keymap_check_system.system())
+        .add_system(command_palette_system.system())
+        .add_system(bundle_palette_system.system())
+        .add_system(bundle_spawn_system.system())
+        .add_system_to_stage(stage::POST_UPDATE, selection_system.system())
+        .add_system_to_stage(stage::POST_UPDATE, ortho_selection_system.system())
+        // after bevy's own `camera_system`, which only knows about `PerspectiveProjection`
+        .add_system_to_stage(stage::POST_UPDATE, camera_projection_system.system())
+        .add_system(transform_session_system.system())
+        .add_system(gizmo_drag_system.system())
+        .add_system(editor_camera_system.system())
+        .add_system(gizmo_follow_system.system())
+        .add_system(status_bar_system.system())
+        .add_system(warning_badge_system.system())
+        .add_system(dependency_panel_system.system());
     let resources = builder.resources_mut();
+    let mut editor = resources.get_mut::<Editor>().unwrap();
+    let keymap = load_keymap(&mut editor);
+    let settings = load_settings(&mut editor);
+    drop(editor);
+    resources.insert(keymap);
+    resources.insert(settings);
     let input_system = InputSystem::default().system(resources);
     builder.add_system(input_system);
     let resources = builder.resources_mut();
     let update_system = UpdateSystem::default().system(resources);
     builder
         .add_system(update_system)
+        .add_system_to_stage(stage::POST_UPDATE, run_actions_system.thread_local_system())
         .add_system_to_stage(stage::POST_UPDATE, apply_system.thread_local_system())
-        .add_system_to_stage(stage::LAST, load_asset_system::<Mesh>.system())
-        .add_system_to_stage(
-            stage::LAST,
-            into_asset_system::<Color, StandardMaterial>.system(),
-        )
-        .add_system_to_stage(
-            stage::LAST,
-            default_component_system::<GlobalTransform>.system(),
-        )
+        .add_system_to_stage(stage::POST_UPDATE, watch_system.thread_local_system())
         .run();
 }
 
+/// Prints the assets of `prefab`, or of every prefab in the asset folder, along with missing
+/// and unreferenced files.  Exits with an error if any asset is missing.
+fn dependency_report(resources: &Resources, prefab: Option<String>) {
+    let registry = resources.get::<TypeRegistry>().unwrap();
+    let root = resources.get::<AssetRoot>().unwrap();
+    let property = registry.property.read();
+
+    let manifest = match &prefab {
+        Some(prefab) => Manifest::from_file(prefab.as_ref(), &root.0, &property),
+        None => Manifest::from_dir(&root.0, &property),
+    };
+    let manifest = manifest.unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+
+    for dependency in &manifest.dependencies {
+        println!(
+            "{}\t{}\t{}",
+            dependency.prefab.display(),
+            dependency.asset_type,
+            dependency.resolved.display()
+        );
+    }
+
+    let mut missing = 0;
+    for dependency in manifest.missing() {
+        missing += 1;
+        eprintln!(
+            "missing: {} (entity {} in {})",
+            dependency.resolved.display(),
+            dependency.entity,
+            dependency.prefab.display()
+        );
+    }
+
+    if prefab.is_none() {
+        for file in manifest.unreferenced(&root.0, &["scn", "ron"]).unwrap() {
+            eprintln!("unreferenced: {}", file.display());
+        }
+    }
+
+    if missing > 0 {
+        process::exit(1);
+    }
+}
+
 fn setup(
     mut commands: Commands,
     _editor: ResMut<EditorCommands>,
+    mut editor: ResMut<Editor>,
     asset_server: Res<AssetServer>,
     registry: Res<TypeRegistry>,
     mut default_bundles: ResMut<DefaultBundles>,
     mut default_properties: ResMut<DefaultProperties>,
+    mut placeholder: ResMut<AssetPlaceholder<Mesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
     let font = asset_server
         .load::<Font, _>("assets/TruenoLight-E2pg.ttf")
         .unwrap();
+    commands.insert_resource(EditorFont(font));
+
+    *placeholder = AssetPlaceholder::new(meshes.add(Mesh::from(shape::Cube { size: 1.0 })));
 
     materials.set(BUTTON_NONE_MATERIAL, Color::rgb(0.5, 0.5, 0.5).into());
     materials.set(BUTTON_HOVERED_MATERIAL, Color::rgb(0.6, 0.6, 0.6).into());
@@ -319,7 +1013,7 @@ fn setup(
         .spawn(NodeComponents {
             style: Style {
                 size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
-                justify_content: JustifyContent::FlexEnd,
+                justify_content: JustifyContent::SpaceBetween,
                 ..Default::default()
             },
             material: materials.add(Color::NONE.into()),
@@ -327,6 +1021,47 @@ fn setup(
         })
         .with_children(|parent| {
             parent
+                .spawn(NodeComponents {
+                    style: Style {
+                        size: Size::new(Val::Percent(15.0), Val::Percent(100.0)),
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_self: AlignSelf::FlexStart,
+                        align_items: AlignItems::FlexStart,
+                        padding: Rect::all(Val::Px(5.0)),
+                        ..Default::default()
+                    },
+                    material: materials.add(Color::rgb(0.2, 0.2, 0.2).into()),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeComponents {
+                            style: Style {
+                                size: Size::new(Val::Percent(100.0), Val::Auto),
+                                margin: Rect {
+                                    bottom: Val::Px(10.0),
+                                    ..Default::default()
+                                },
+                                flex_direction: FlexDirection::ColumnReverse,
+                                align_items: AlignItems::FlexStart,
+                                ..Default::default()
+                            },
+                            material: materials.add(Color::NONE.into()),
+                            ..Default::default()
+                        })
+                        .with(BundlePalette)
+                        .spawn(NodeComponents {
+                            style: Style {
+                                size: Size::new(Val::Percent(100.0), Val::Auto),
+                                flex_direction: FlexDirection::ColumnReverse,
+                                align_items: AlignItems::FlexStart,
+                                ..Default::default()
+                            },
+                            material: materials.add(Color::NONE.into()),
+                            ..Default::default()
+                        })
+                        .with(OutlinerPanel);
+                })
                 .spawn(NodeComponents {
                     style: Style {
                         size: Size::new(Val::Percent(20.0), Val::Percent(100.0)),
@@ -379,6 +1114,7 @@ fn setup(
                         })
                         .with(ButtonFunction::AddComponent)
                         .with(ButtonToggled::default())
+                        .with(TextField::default())
                         .with_children(|parent| {
                             parent.spawn(TextComponents {
                                 text: Text {
@@ -391,188 +1127,2284 @@ fn setup(
                                 },
                                 ..Default::default()
                             });
-                        });
+                        })
+                        .spawn(NodeComponents {
+                            style: Style {
+                                size: Size::new(Val::Percent(90.0), Val::Auto),
+                                margin: Rect::all(Val::Px(5.0)),
+                                flex_direction: FlexDirection::ColumnReverse,
+                                align_items: AlignItems::FlexStart,
+                                ..Default::default()
+                            },
+                            material: materials.add(Color::NONE.into()),
+                            ..Default::default()
+                        })
+                        .with(AutocompletePanel)
+                        .spawn(NodeComponents {
+                            style: Style {
+                                size: Size::new(Val::Percent(90.0), Val::Auto),
+                                margin: Rect::all(Val::Px(5.0)),
+                                flex_direction: FlexDirection::ColumnReverse,
+                                align_items: AlignItems::FlexStart,
+                                ..Default::default()
+                            },
+                            material: materials.add(Color::NONE.into()),
+                            ..Default::default()
+                        })
+                        .with(InspectorPanel)
+                        .spawn(NodeComponents {
+                            style: Style {
+                                size: Size::new(Val::Percent(90.0), Val::Auto),
+                                margin: Rect::all(Val::Px(5.0)),
+                                flex_direction: FlexDirection::ColumnReverse,
+                                align_items: AlignItems::FlexStart,
+                                ..Default::default()
+                            },
+                            material: materials.add(Color::NONE.into()),
+                            ..Default::default()
+                        })
+                        .with(DependencyPanel);
                 });
-        });
-
-    let bundles_path: &Path = "assets/editor_bundles.ron".as_ref();
-    let properties_path: &Path = "assets/editor_properties.ron".as_ref();
-
-    let property = registry.property.read();
+        })
+        .spawn(NodeComponents {
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Percent(30.0),
+                    bottom: Val::Percent(10.0),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Percent(40.0), Val::Auto),
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                padding: Rect::all(Val::Px(5.0)),
+                ..Default::default()
+            },
+            material: materials.add(Color::rgb(0.15, 0.15, 0.15).into()),
+            ..Default::default()
+        })
+        .with(CommandPaletteRoot)
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonComponents {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Px(30.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    material: BUTTON_NONE_MATERIAL,
+                    ..Default::default()
+                })
+                .with(ButtonFunction::CommandPalette)
+                .with(ButtonToggled::default())
+                .with(TextField::default())
+                .with_children(|parent| {
+                    parent.spawn(TextComponents {
+                        text: Text {
+                            value: String::new(),
+                            font,
+                            style: TextStyle {
+                                font_size: 20.0,
+                                color: Color::rgb(0.8, 0.8, 0.8),
+                            },
+                        },
+                        ..Default::default()
+                    });
+                })
+                .spawn(NodeComponents {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Auto),
+                        margin: Rect::all(Val::Px(5.0)),
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::FlexStart,
+                        ..Default::default()
+                    },
+                    material: materials.add(Color::NONE.into()),
+                    ..Default::default()
+                })
+                .with(CommandPaletteList);
+        })
+        .spawn(NodeComponents {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Percent(15.0),
+                    top: Val::Px(0.0),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Percent(65.0), Val::Px(25.0)),
+                align_items: AlignItems::Center,
+                padding: Rect::all(Val::Px(5.0)),
+                ..Default::default()
+            },
+            material: materials.add(Color::rgba(0.1, 0.1, 0.1, 0.8).into()),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(TextComponents {
+                    text: Text {
+                        value: String::new(),
+                        font,
+                        style: TextStyle {
+                            font_size: 16.0,
+                            color: Color::rgb(0.8, 0.8, 0.8),
+                        },
+                    },
+                    ..Default::default()
+                })
+                .with(StatusBar);
+        });
+
+    let property = registry.property.read();
+
+    if let Some(dynamic) = read_library(BUNDLES_PATH.as_ref(), &property, &mut editor) {
+        *default_bundles = DefaultBundles::from_dynamic(&dynamic);
+    }
+
+    if let Some(dynamic) = read_library(PROPERTIES_PATH.as_ref(), &property, &mut editor) {
+        default_properties.extend(DefaultProperties::from_dynamic(&dynamic));
+    }
+}
+
+/// Reads the keymap, falling back to the default one and saying so in the status bar.
+fn load_keymap(editor: &mut Editor) -> Keymap {
+    let path = Path::new(KEYMAP_PATH);
+    if !path.exists() {
+        return Keymap::default();
+    }
+    match Keymap::from_file(path) {
+        Ok(keymap) => keymap,
+        Err(err) => {
+            editor.notice = Some(format!(
+                "failed to load the keymap, using the default one: {}",
+                err
+            ));
+            Keymap::default()
+        }
+    }
+}
+
+/// Reads the settings, falling back to the defaults and saying so in the status bar.
+fn load_settings(editor: &mut Editor) -> EditorSettings {
+    let path = Path::new(SETTINGS_PATH);
+    if !path.exists() {
+        return EditorSettings::default();
+    }
+    match EditorSettings::from_file(path) {
+        Ok(settings) => settings,
+        Err(err) => {
+            editor.notice = Some(format!(
+                "failed to load the settings, using the defaults: {}",
+                err
+            ));
+            EditorSettings::default()
+        }
+    }
+}
+
+/// Reads a bundle or property library, or shows why it couldn't be read in the status bar.
+fn read_library(
+    path: &Path,
+    property: &PropertyTypeRegistry,
+    editor: &mut Editor,
+) -> Option<DynamicProperties> {
+    if !path.exists() {
+        return None;
+    }
+    let result = fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|text| {
+            let mut deserializer =
+                ::ron::Deserializer::from_str(&text).map_err(|err| err.to_string())?;
+            let mut deserializer = erased_serde::Deserializer::erase(&mut deserializer);
+            DynamicProperties::deserialize(&mut deserializer, property)
+                .map(|dynamic| dynamic.as_properties().unwrap().to_dynamic())
+                .map_err(|err| err.to_string())
+        });
+    match result {
+        Ok(dynamic) => Some(dynamic),
+        Err(err) => {
+            editor.notice = Some(format!("failed to read {}: {}", path.display(), err));
+            None
+        }
+    }
+}
+
+fn setup_gizmo(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let cube = meshes.add(Mesh::from(shape::Cube { size: 1.0 }));
+    let ring = meshes.add(ring_mesh(1.2, 0.02, 48, 6));
+
+    commands
+        .spawn((Gizmo, Transform::default(), GlobalTransform::default()))
+        .with_children(|parent| {
+            for &axis in &[Axis::X, Axis::Y, Axis::Z] {
+                let color = match axis {
+                    Axis::X => Color::rgb(0.9, 0.2, 0.2),
+                    Axis::Y => Color::rgb(0.2, 0.9, 0.2),
+                    Axis::Z => Color::rgb(0.2, 0.3, 0.9),
+                };
+                let rotation = axis_rotation(axis);
+                // meshes are built along Y, then rotated onto the axis
+                let handles = [
+                    (TransformMode::Translate, cube, Vec3::new(0.04, 1.0, 0.04), 0.5),
+                    (TransformMode::Rotate, ring, Vec3::one(), 0.0),
+                    (TransformMode::Scale, cube, Vec3::splat(0.12), 1.0),
+                ];
+                for &(mode, mesh, scale, offset) in &handles {
+                    parent
+                        .spawn(PbrComponents {
+                            mesh,
+                            material: materials.add(StandardMaterial {
+                                albedo: color,
+                                shaded: false,
+                                ..Default::default()
+                            }),
+                            transform: Transform::new(Mat4::from_scale_rotation_translation(
+                                scale,
+                                rotation,
+                                rotation * Vec3::new(0.0, offset, 0.0),
+                            )),
+                            ..Default::default()
+                        })
+                        .with(GizmoHandle { mode, axis })
+                        .with(PickableMesh::default())
+                        .with(HighlightablePickMesh::default());
+                }
+            }
+        });
+}
+
+fn setup_thread_local(world: &mut World, resources: &mut Resources) {
+    let mut editor = resources.get_mut::<Editor>().unwrap();
+    let path = env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| editor.path.clone());
+    if let Err(err) = editor.read(&path, world, resources) {
+        // the editor keeps its empty scene, which saving would write over the file
+        editor.notice = Some(format!(
+            "failed to read {}, saving will overwrite it: {}",
+            path.display(),
+            err
+        ));
+    }
+}
+
+fn watch_system(world: &mut World, resources: &mut Resources) {
+    let mut watcher = resources.get_mut::<FileWatcher>().unwrap();
+    let time = resources.get::<Time>().unwrap();
+    watcher.timer.tick(time.delta_seconds);
+    if !watcher.timer.finished {
+        return;
+    }
+
+    let mut editor = resources.get_mut::<Editor>().unwrap();
+    let path = editor.path.clone();
+    if watcher.changed(&path) {
+        if editor.dirty {
+            editor.notice = Some(format!(
+                "{} changed on disk, saving will overwrite it",
+                path.display()
+            ));
+        } else if let Err(err) = editor.reload(world, resources) {
+            editor.notice = Some(format!("failed to reload {}: {}", path.display(), err));
+        }
+    }
+
+    let registry = resources.get::<TypeRegistry>().unwrap();
+    let property = registry.property.read();
+
+    if watcher.changed(BUNDLES_PATH.as_ref()) {
+        if let Some(dynamic) = read_library(BUNDLES_PATH.as_ref(), &property, &mut editor) {
+            *resources.get_mut::<DefaultBundles>().unwrap() = DefaultBundles::from_dynamic(&dynamic);
+        }
+    }
+
+    if watcher.changed(PROPERTIES_PATH.as_ref()) {
+        if let Some(dynamic) = read_library(PROPERTIES_PATH.as_ref(), &property, &mut editor) {
+            resources
+                .get_mut::<DefaultProperties>()
+                .unwrap()
+                .extend(DefaultProperties::from_dynamic(&dynamic));
+        }
+    }
+
+    if watcher.changed(KEYMAP_PATH.as_ref()) {
+        match Keymap::from_file(KEYMAP_PATH.as_ref()) {
+            Ok(keymap) => *resources.get_mut::<Keymap>().unwrap() = keymap,
+            Err(err) => {
+                editor.notice = Some(format!(
+                    "failed to reload the keymap, keeping the old one: {}",
+                    err
+                ));
+            }
+        }
+    }
+
+    if watcher.changed(SETTINGS_PATH.as_ref()) {
+        match EditorSettings::from_file(SETTINGS_PATH.as_ref()) {
+            Ok(settings) => *resources.get_mut::<EditorSettings>().unwrap() = settings,
+            Err(err) => {
+                editor.notice = Some(format!(
+                    "failed to reload the settings, keeping the old ones: {}",
+                    err
+                ));
+            }
+        }
+    }
+}
+
+fn button_system(
+    mut query: Query<
+        With<
+            Button,
+            (
+                Mutated<Interaction>,
+                Option<Mut<ButtonToggled>>,
+                Mut<Handle<ColorMaterial>>,
+                &ButtonFunction,
+            ),
+        >,
+    >,
+) {
+    for (interaction, mut toggled, mut material, _) in &mut query.iter() {
+        match *interaction {
+            Interaction::Clicked => {
+                if let Some(toggled) = &mut toggled {
+                    toggled.0 = !toggled.0;
+                    if toggled.0 {
+                        *material = BUTTON_TOGGLED_MATERIAL
+                    } else {
+                        *material = BUTTON_CLICKED_MATERIAL
+                    }
+                } else {
+                    *material = BUTTON_CLICKED_MATERIAL
+                }
+            }
+            Interaction::Hovered => *material = BUTTON_HOVERED_MATERIAL,
+            Interaction::None => *material = BUTTON_NONE_MATERIAL,
+        }
+    }
+}
+
+fn button_enter_system(
+    input: Res<Input<KeyCode>>,
+    keymap: Res<Keymap>,
+    focus: Res<TextFocus>,
+    mut query: Query<With<Button, (Mut<ButtonToggled>, &ButtonFunction)>>,
+) {
+    for (mut toggled, _) in &mut query.iter() {
+        if toggled.0 {
+            if keymap.just_pressed("field_confirm", &input, &focus) {
+                toggled.0 = false;
+            }
+        }
+    }
+}
+
+fn text_button_system(
+    input: Res<Input<KeyCode>>,
+    keymap: Res<Keymap>,
+    focus: Res<TextFocus>,
+    default_properties: Res<DefaultProperties>,
+    prefab: Res<Editor>,
+    scenes: Res<Assets<Scene>>,
+    mut editor: ResMut<EditorCommands>,
+    mut query: Query<With<Button, (&ButtonToggled, &ButtonFunction, &TextField)>>,
+    mut mutated: Query<
+        With<
+            Button,
+            (
+                Mutated<ButtonToggled>,
+                &ButtonFunction,
+                &Children,
+                Mut<TextField>,
+                Option<&InspectorField>,
+            ),
+        >,
+    >,
+    texts: Query<Mut<Text>>,
+    mut selected: Query<(&Widget, &Selected)>,
+    mut autocomplete: ResMut<Autocomplete>,
+) {
+    for (toggled, function, children, mut text_field, field) in &mut mutated.iter() {
+        if toggled.0 {
+            match function {
+                ButtonFunction::Save
+                | ButtonFunction::SpawnBundle
+                | ButtonFunction::CommandPalette => {}
+                ButtonFunction::AddComponent => {
+                    text_field.set("");
+                    autocomplete.error = None;
+                    autocomplete.update(default_properties.search(""));
+                }
+                ButtonFunction::EditProperty => {
+                    text_field.set(field.unwrap().value.clone());
+                }
+            }
+            continue;
+        }
+
+        match function {
+            ButtonFunction::Save | ButtonFunction::SpawnBundle | ButtonFunction::CommandPalette => {}
+            ButtonFunction::AddComponent => {
+                for &child in children.iter() {
+                    texts.get_mut::<Text>(child).unwrap().value = "Add component".to_string();
+                }
+                let typed = text_field.text();
+                if text_field.cancelled || typed.is_empty() {
+                    autocomplete.clear();
+                    continue;
+                }
+                let mut selection = vec![];
+                for (widget, selected) in &mut selected.iter() {
+                    if selected.0 {
+                        selection.push(widget.0);
+                    }
+                }
+                let added = match autocomplete.selected() {
+                    Some(name) => add_component(name, &default_properties, &mut editor, &selection),
+                    None => false,
+                };
+                if !added {
+                    autocomplete.error = Some(format!("No component matches \"{}\"", typed));
+                }
+                autocomplete.clear();
+            }
+            ButtonFunction::EditProperty => {
+                let field = field.unwrap();
+                let scene = scenes.get(&prefab.scene).unwrap();
+                let written =
+                    !text_field.cancelled && field.write(text_field.text(), scene, &mut editor);
+                if !written {
+                    for &child in children.iter() {
+                        texts.get_mut::<Text>(child).unwrap().value =
+                            format!("{}{}", field.label, field.value);
+                    }
+                }
+            }
+        }
+    }
+
+    for (toggled, function, text_field) in &mut query.iter() {
+        if let (true, ButtonFunction::AddComponent) = (toggled.0, function) {
+            autocomplete.update(default_properties.search(text_field.text()));
+            if keymap.just_pressed("field_up", &input, &focus) {
+                autocomplete.up();
+            }
+            if keymap.just_pressed("field_down", &input, &focus) {
+                autocomplete.down();
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct TextFieldState {
+    reader: EventReader<ReceivedCharacter>,
+}
+
+/// Edits the focused text field, which is the toggled button that has a `TextField`.
+fn text_field_system(
+    mut state: Local<TextFieldState>,
+    input: Res<Input<KeyCode>>,
+    keymap: Res<Keymap>,
+    characters: Res<Events<ReceivedCharacter>>,
+    mode: Res<EditorMode>,
+    mut editor: ResMut<Editor>,
+    mut focus: ResMut<TextFocus>,
+    mut fields: Query<(Mut<ButtonToggled>, Mut<TextField>, &Children)>,
+    texts: Query<Mut<Text>>,
+) {
+    let typed = state
+        .reader
+        .iter(&characters)
+        .map(|event| event.char)
+        .filter(|c| !c.is_control())
+        .collect::<String>();
+    let control = input.pressed(KeyCode::LControl) || input.pressed(KeyCode::RControl);
+    let shift = input.pressed(KeyCode::LShift) || input.pressed(KeyCode::RShift);
+
+    // a value typed into a transform has the keyboard too
+    focus.0 = !mode.entry.is_empty();
+    for (mut toggled, mut field, children) in &mut fields.iter() {
+        if !toggled.0 {
+            continue;
+        }
+        focus.0 = true;
+
+        if !control {
+            field.insert(&typed);
+        }
+        if input.just_pressed(KeyCode::Back) {
+            field.backspace();
+        }
+        if input.just_pressed(KeyCode::Delete) {
+            field.delete();
+        }
+        if input.just_pressed(KeyCode::Left) {
+            field.left(shift);
+        }
+        if input.just_pressed(KeyCode::Right) {
+            field.right(shift);
+        }
+        if input.just_pressed(KeyCode::Home) {
+            field.home(shift);
+        }
+        if input.just_pressed(KeyCode::End) {
+            field.end(shift);
+        }
+        if control && input.just_pressed(KeyCode::A) {
+            field.select_all();
+        }
+        if control && (input.just_pressed(KeyCode::C) || input.just_pressed(KeyCode::X)) {
+            if let Some(selected) = field.selected_text() {
+                let copied = ClipboardContext::new()
+                    .and_then(|mut clipboard| clipboard.set_contents(selected.to_string()));
+                if let Err(err) = copied {
+                    editor.notice = Some(format!("failed to copy to the clipboard: {}", err));
+                } else if input.just_pressed(KeyCode::X) {
+                    field.delete();
+                }
+            }
+        }
+        if control && input.just_pressed(KeyCode::V) {
+            match ClipboardContext::new().and_then(|mut clipboard| clipboard.get_contents()) {
+                Ok(contents) => {
+                    let contents = contents.lines().collect::<Vec<_>>().join(" ");
+                    field.insert(&contents);
+                }
+                Err(err) => {
+                    editor.notice = Some(format!("failed to paste from the clipboard: {}", err));
+                }
+            }
+        }
+        if keymap.just_pressed("field_cancel", &input, &focus) {
+            field.cancelled = true;
+            toggled.0 = false;
+            continue;
+        }
+
+        let display = field.display();
+        for &child in children.iter() {
+            let mut text = texts.get_mut::<Text>(child).unwrap();
+            if text.value != display {
+                text.value = display.clone();
+            }
+        }
+    }
+}
+
+fn autocomplete_system(
+    mut commands: Commands,
+    mut shown: Local<Autocomplete>,
+    autocomplete: Res<Autocomplete>,
+    font: Res<EditorFont>,
+    mut panels: Query<With<AutocompletePanel, (Entity, Option<&Children>)>>,
+) {
+    if *shown == *autocomplete {
+        return;
+    }
+    *shown = autocomplete.clone();
+
+    for (panel, children) in &mut panels.iter() {
+        if let Some(children) = children {
+            for &child in children.iter() {
+                commands.despawn_recursive(child);
+            }
+        }
+
+        let rows = spawn_autocomplete_rows(&mut commands, font.0, &autocomplete);
+        commands.push_children(panel, &rows);
+    }
+}
+
+fn spawn_autocomplete_rows(
+    commands: &mut Commands,
+    font: Handle<Font>,
+    autocomplete: &Autocomplete,
+) -> Vec<Entity> {
+    let mut rows = vec![];
+    if let Some(error) = &autocomplete.error {
+        rows.push(spawn_text(
+            commands,
+            font,
+            error.clone(),
+            Color::rgb(1.0, 0.4, 0.4),
+        ));
+    }
+    for (index, name) in autocomplete.matches.iter().enumerate() {
+        let color = if index == autocomplete.cursor {
+            Color::rgb(1.0, 1.0, 1.0)
+        } else {
+            Color::rgb(0.6, 0.6, 0.6)
+        };
+        rows.push(spawn_text(commands, font, name.clone(), color));
+    }
+    rows
+}
+
+/// Flips `bool` fields of the inspector, which don't need any typing.
+fn inspector_toggle_system(
+    prefab: Res<Editor>,
+    scenes: Res<Assets<Scene>>,
+    mut editor: ResMut<EditorCommands>,
+    mut query: Query<
+        Without<ButtonToggled, (Mutated<Interaction>, &ButtonFunction, &InspectorField)>,
+    >,
+) {
+    for (interaction, _, field) in &mut query.iter() {
+        if let Interaction::Clicked = *interaction {
+            let scene = scenes.get(&prefab.scene).unwrap();
+            let value = if field.value == "true" { "false" } else { "true" };
+            field.write(value, scene, &mut editor);
+        }
+    }
+}
+
+#[derive(Default)]
+struct InspectorState {
+    reader: EventReader<AssetEvent<Scene>>,
+    selected: Option<u32>,
+}
+
+fn inspector_system(
+    mut commands: Commands,
+    mut state: Local<InspectorState>,
+    events: Res<Events<AssetEvent<Scene>>>,
+    editor: Res<Editor>,
+    font: Res<EditorFont>,
+    assets: Res<Assets<Scene>>,
+    mut selected: Query<(&Widget, &Selected)>,
+    mut panels: Query<With<InspectorPanel, (Entity, Option<&Children>)>>,
+) {
+    let mut changed = false;
+    for event in state.reader.iter(&events) {
+        match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                changed |= *handle == editor.scene;
+            }
+            AssetEvent::Removed { .. } => {}
+        }
+    }
+
+    let mut selection = None;
+    for (widget, select) in &mut selected.iter() {
+        if select.0 {
+            selection = Some(widget.0);
+            break;
+        }
+    }
+    if selection != state.selected {
+        state.selected = selection;
+        changed = true;
+    }
+    if !changed {
+        return;
+    }
+
+    let scene_entity = selection.and_then(|id| {
+        assets
+            .get(&editor.scene)
+            .and_then(|scene| scene.entities.get(id as usize))
+    });
+
+    for (panel, children) in &mut panels.iter() {
+        if let Some(children) = children {
+            for &child in children.iter() {
+                commands.despawn_recursive(child);
+            }
+        }
+
+        let scene_entity = match scene_entity {
+            Some(scene_entity) => scene_entity,
+            None => continue,
+        };
+
+        let mut rows = vec![];
+        for (index, component) in scene_entity.components.iter().enumerate() {
+            for row in inspect(component) {
+                rows.push(spawn_inspector_row(
+                    &mut commands,
+                    font.0,
+                    &row,
+                    scene_entity.entity,
+                    index,
+                ));
+            }
+        }
+        commands.push_children(panel, &rows);
+    }
+}
+
+/// Mirrors clicks in the viewport into `Selected`.
+///
+/// Clicks on the UI also deselect every mesh, so they're ignored.
+fn selection_system(
+    gizmo: Res<GizmoState>,
+    editor_camera: Res<EditorCamera>,
+    mut interactions: Query<&Interaction>,
+    mut picked: Query<(Mutated<SelectablePickMesh>, Mut<Selected>)>,
+) {
+    // clicking a gizmo handle deselects everything else as far as picking is concerned, and
+    // orthographic views are picked by `ortho_selection_system`
+    if gizmo.drag.is_some() || editor_camera.orthographic {
+        return;
+    }
+    for interaction in &mut interactions.iter() {
+        match interaction {
+            Interaction::None => {}
+            _ => return,
+        }
+    }
+    for (select, mut selected) in &mut picked.iter() {
+        if selected.0 != select.selected() {
+            selected.0 = select.selected();
+        }
+    }
+}
+
+/// Selects the widget clicked in orthographic views, where `PickState` can't pick.
+///
+/// Like picking, clicking nothing deselects everything.
+fn ortho_selection_system(
+    mut cursor: Local<GizmoCursor>,
+    gizmo: Res<GizmoState>,
+    editor_camera: Res<EditorCamera>,
+    mouse: Res<Input<MouseButton>>,
+    cursor_moved: Res<Events<CursorMoved>>,
+    windows: Res<Windows>,
+    meshes: Res<Assets<Mesh>>,
+    mut interactions: Query<&Interaction>,
+    mut cameras: Query<With<FlyCamera, (&Camera, &GlobalTransform)>>,
+    mut widgets: Query<
+        With<
+            Widget,
+            (
+                Entity,
+                &GlobalTransform,
+                Option<&Handle<Mesh>>,
+                Mut<Selected>,
+                Mut<SelectablePickMesh>,
+            ),
+        >,
+    >,
+) {
+    if let Some(moved) = cursor.reader.latest(&cursor_moved) {
+        cursor.position = Some(moved.position);
+    }
+    if !editor_camera.orthographic
+        || gizmo.drag.is_some()
+        || !mouse.just_pressed(MouseButton::Left)
+    {
+        return;
+    }
+    for interaction in &mut interactions.iter() {
+        match interaction {
+            Interaction::None => {}
+            _ => return,
+        }
+    }
+    let ray = match cursor_ray(cursor.position, &windows, &mut cameras) {
+        Some(ray) => ray,
+        None => return,
+    };
+
+    let picked = ray_pick(
+        &ray,
+        &meshes,
+        (&mut widgets.iter())
+            .filter_map(|(entity, transform, mesh, _, _)| Some((entity, transform, mesh?))),
+    );
+    for (entity, _, _, mut selected, mut pick) in &mut widgets.iter() {
+        set_selected(&mut selected, &mut pick, picked == Some(entity));
+    }
+}
+
+#[derive(Default)]
+struct OutlinerState {
+    reader: EventReader<AssetEvent<Scene>>,
+}
+
+fn outliner_system(
+    mut commands: Commands,
+    mut state: Local<OutlinerState>,
+    events: Res<Events<AssetEvent<Scene>>>,
+    editor: Res<Editor>,
+    font: Res<EditorFont>,
+    assets: Res<Assets<Scene>>,
+    mut widgets: Query<(&Widget, &Selected)>,
+    mut panels: Query<With<OutlinerPanel, (Entity, Option<&Children>)>>,
+    mut entries: Query<(&OutlinerEntry, Mut<Handle<ColorMaterial>>)>,
+) {
+    let mut changed = false;
+    for event in state.reader.iter(&events) {
+        match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                changed |= *handle == editor.scene;
+            }
+            AssetEvent::Removed { .. } => {}
+        }
+    }
+
+    let mut selection = vec![];
+    for (widget, selected) in &mut widgets.iter() {
+        if selected.0 {
+            selection.push(widget.0);
+        }
+    }
+
+    if !changed {
+        for (&OutlinerEntry(entity), mut material) in &mut entries.iter() {
+            let expected = if selection.contains(&entity) {
+                BUTTON_TOGGLED_MATERIAL
+            } else {
+                BUTTON_NONE_MATERIAL
+            };
+            if *material != expected {
+                *material = expected;
+            }
+        }
+        return;
+    }
+
+    let scene = match assets.get(&editor.scene) {
+        Some(scene) => scene,
+        None => return,
+    };
+    for (panel, children) in &mut panels.iter() {
+        if let Some(children) = children {
+            for &child in children.iter() {
+                commands.despawn_recursive(child);
+            }
+        }
+
+        let mut rows = vec![];
+        for row in outline(scene) {
+            let material = if selection.contains(&row.entity) {
+                BUTTON_TOGGLED_MATERIAL
+            } else {
+                BUTTON_NONE_MATERIAL
+            };
+            commands
+                .spawn(ButtonComponents {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Px(20.0)),
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    material,
+                    ..Default::default()
+                })
+                .with(OutlinerEntry(row.entity))
+                .with_children(|parent| {
+                    parent.spawn(TextComponents {
+                        text: Text {
+                            value: format!(
+                                "{}{} ({})",
+                                "  ".repeat(row.depth),
+                                row.name,
+                                row.components.join(", ")
+                            ),
+                            font: font.0,
+                            style: TextStyle {
+                                font_size: 16.0,
+                                color: Color::rgb(0.8, 0.8, 0.8),
+                            },
+                        },
+                        ..Default::default()
+                    });
+                });
+            rows.push(commands.current_entity().unwrap());
+        }
+        commands.push_children(panel, &rows);
+    }
+}
+
+fn outliner_click_system(
+    mut clicked: Query<(Mutated<Interaction>, &OutlinerEntry)>,
+    mut widgets: Query<(&Widget, Mut<Selected>, Mut<SelectablePickMesh>)>,
+) {
+    for (interaction, &OutlinerEntry(entity)) in &mut clicked.iter() {
+        if let Interaction::Clicked = *interaction {
+            for (widget, mut selected, mut pick) in &mut widgets.iter() {
+                set_selected(&mut selected, &mut pick, widget.0 == entity);
+            }
+        }
+    }
+}
+
+/// Selects or deselects a widget from outside the viewport's picking.
+///
+/// `SelectablePickMesh` can't be selected from outside the picking plugin, only reset, so
+/// deselected widgets are reset to stop the viewport from still showing them as selected.
+/// The reset is then mirrored back by `selection_system` as a deselection, which agrees.
+fn set_selected(selected: &mut Mut<Selected>, pick: &mut Mut<SelectablePickMesh>, select: bool) {
+    if selected.0 != select {
+        selected.0 = select;
+    }
+    if !select && pick.selected() {
+        **pick = SelectablePickMesh::default();
+    }
+}
+
+fn bundle_palette_system(
+    mut commands: Commands,
+    mut shown: Local<Vec<String>>,
+    default_bundles: Res<DefaultBundles>,
+    font: Res<EditorFont>,
+    mut panels: Query<With<BundlePalette, (Entity, Option<&Children>)>>,
+) {
+    let names = default_bundles
+        .search("")
+        .into_iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    if *shown == names {
+        return;
+    }
+
+    for (panel, children) in &mut panels.iter() {
+        if let Some(children) = children {
+            for &child in children.iter() {
+                commands.despawn_recursive(child);
+            }
+        }
+
+        let mut rows = vec![spawn_text(
+            &mut commands,
+            font.0,
+            "Bundles".to_string(),
+            Color::rgb(0.8, 0.8, 0.8),
+        )];
+        for name in &names {
+            commands
+                .spawn(ButtonComponents {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Px(20.0)),
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    material: BUTTON_NONE_MATERIAL,
+                    ..Default::default()
+                })
+                .with(ButtonFunction::SpawnBundle)
+                .with(PaletteEntry(name.clone()))
+                .with_children(|parent| {
+                    parent.spawn(TextComponents {
+                        text: Text {
+                            value: format!("+ {}", name),
+                            font: font.0,
+                            style: TextStyle {
+                                font_size: 16.0,
+                                color: Color::rgb(0.8, 0.8, 0.8),
+                            },
+                        },
+                        ..Default::default()
+                    });
+                });
+            rows.push(commands.current_entity().unwrap());
+        }
+        commands.push_children(panel, &rows);
+    }
+    *shown = names;
+}
+
+/// Spawns the clicked palette bundle in front of the camera and selects it.
+fn bundle_spawn_system(
+    default_bundles: Res<DefaultBundles>,
+    registry: Res<TypeRegistry>,
+    mut editor: ResMut<EditorCommands>,
+    mut clicked: Query<(Mutated<Interaction>, &PaletteEntry)>,
+    mut cameras: Query<With<FlyCamera, &GlobalTransform>>,
+) {
+    for (interaction, entry) in &mut clicked.iter() {
+        if let Interaction::Clicked = *interaction {
+            let mut camera = None;
+            for transform in &mut cameras.iter() {
+                camera = Some(*transform);
+            }
+            spawn_bundle(
+                &entry.0,
+                &default_bundles,
+                &registry,
+                &mut editor,
+                camera.as_ref(),
+            );
+        }
+    }
+}
+
+/// Spawns the `DefaultBundles` entry `name` in front of `camera` and selects it.
+fn spawn_bundle(
+    name: &str,
+    default_bundles: &DefaultBundles,
+    registry: &TypeRegistry,
+    editor: &mut EditorCommands,
+    camera: Option<&GlobalTransform>,
+) {
+    let mut bundle = match default_bundles.get(name) {
+        Some(bundle) => bundle,
+        None => return,
+    };
+    let translation = camera.map_or(Vec3::zero(), |camera| {
+        let forward = camera.value().transform_vector3(-Vec3::unit_z()).normalize();
+        camera.translation() + forward * SPAWN_DISTANCE
+    });
+    bundle.add(
+        Transform::from_translation(translation).to_dynamic(),
+        &registry.component.read(),
+    );
+
+    editor
+        .spawn(bundle.clone())
+        .with_spawned(move |entity, world, resources| {
+            select_only(world, &resources.get::<Editor>().unwrap(), entity);
+            let mut history = resources.get_mut::<EditorHistory>().unwrap();
+            history.push(HistoryEntry::Spawn { entity, bundle });
+        });
+}
+
+/// Adds the `DefaultProperties` entry `name` to the entities in `selection`.
+fn add_component(
+    name: &str,
+    default_properties: &DefaultProperties,
+    editor: &mut EditorCommands,
+    selection: &[u32],
+) -> bool {
+    let component = match default_properties.get(name) {
+        Some(component) => component,
+        None => return false,
+    };
+    for &entity in selection {
+        editor.insert_one(
+            entity,
+            component.clone_prop().as_properties().unwrap().to_dynamic(),
+        );
+        editor.sync_one_to_world(entity, component.type_name.clone());
+    }
+    true
+}
+
+fn selected_widgets(world: &World) -> Vec<u32> {
+    world
+        .query::<(&Widget, &Selected)>()
+        .iter()
+        .filter(|(_, selected)| selected.0)
+        .map(|(widget, _)| widget.0)
+        .collect()
+}
+
+fn add_editor_actions(builder: &mut AppBuilder) {
+    builder
+        .add_editor_action(EditorAction::new("save", "Save prefab", |_, resources| {
+            let mut editor = resources.get_mut::<Editor>().unwrap();
+            let path = editor.path.clone();
+            save(
+                &mut editor,
+                path,
+                &resources.get::<TypeRegistry>().unwrap(),
+                &mut resources.get_mut::<Assets<Scene>>().unwrap(),
+                &resources.get::<DefaultBundles>().unwrap(),
+                &resources.get::<DefaultProperties>().unwrap(),
+                &mut resources.get_mut::<FileWatcher>().unwrap(),
+            );
+        }))
+        .add_editor_action(EditorAction::new("undo", "Undo", |_, resources| {
+            let mut editor = resources.get_mut::<EditorCommands>().unwrap();
+            resources.get_mut::<EditorHistory>().unwrap().undo(&mut editor);
+        }))
+        .add_editor_action(EditorAction::new("redo", "Redo", |_, resources| {
+            let mut editor = resources.get_mut::<EditorCommands>().unwrap();
+            resources.get_mut::<EditorHistory>().unwrap().redo(&mut editor);
+        }))
+        .add_editor_action(EditorAction::new(
+            "delete",
+            "Delete selected entities",
+            |world, resources| {
+                let mut editor = resources.get_mut::<EditorCommands>().unwrap();
+                let mut history = resources.get_mut::<EditorHistory>().unwrap();
+                let scene_handle = resources.get::<Editor>().unwrap().scene;
+                let assets = resources.get::<Assets<Scene>>().unwrap();
+                let scene = assets.get(&scene_handle).unwrap();
+                let mut selection = selected_widgets(world);
+                selection.sort_unstable_by(|a, b| b.cmp(a));
+                // each despawn takes the children along and shifts the later ids down
+                let mut removed = Vec::<u32>::new();
+                for entity in selection {
+                    if removed.contains(&entity) {
+                        continue;
+                    }
+                    let shift = removed.iter().filter(|&&other| other < entity).count() as u32;
+                    editor.despawn(entity - shift);
+                    removed.extend(scene_subtree(scene, entity));
+                }
+                removed.sort_unstable_by(|a, b| b.cmp(a));
+                removed.dedup();
+                for entity in removed {
+                    history.despawned(entity);
+                }
+            },
+        ))
+        .add_editor_action(EditorAction::new(
+            "toggle_fly_camera",
+            "Toggle fly camera",
+            |world, resources| {
+                let mut editor_camera = resources.get_mut::<EditorCamera>().unwrap();
+                for camera in fly_cameras(world) {
+                    let rotation = world.get::<Transform>(camera).unwrap().rotation();
+                    let mut fly_camera = world.get_mut::<FlyCamera>(camera).unwrap();
+                    fly_camera.enabled = !fly_camera.enabled;
+                    if fly_camera.enabled {
+                        editor_camera.orbiting = false;
+                        sync_fly_camera(&mut fly_camera, rotation);
+                    }
+                }
+            },
+        ))
+        .add_editor_action(EditorAction::new(
+            "toggle_orbit_camera",
+            "Toggle orbit camera",
+            |world, resources| {
+                let mut editor_camera = resources.get_mut::<EditorCamera>().unwrap();
+                editor_camera.orbiting = !editor_camera.orbiting;
+                for camera in fly_cameras(world) {
+                    let transform = *world.get::<Transform>(camera).unwrap();
+                    let mut fly_camera = world.get_mut::<FlyCamera>(camera).unwrap();
+                    fly_camera.enabled = !editor_camera.orbiting;
+                    if editor_camera.orbiting {
+                        let distance = editor_camera.orbit.distance;
+                        editor_camera.orbit = orbit_in_front(&transform, distance);
+                    } else {
+                        sync_fly_camera(&mut fly_camera, transform.rotation());
+                    }
+                }
+            },
+        ))
+        .add_editor_action(EditorAction::new(
+            "view_front",
+            "Front view",
+            |world, resources| set_ortho_view(world, resources, Some(OrthoView::Front)),
+        ))
+        .add_editor_action(EditorAction::new(
+            "view_side",
+            "Side view",
+            |world, resources| set_ortho_view(world, resources, Some(OrthoView::Side)),
+        ))
+        .add_editor_action(EditorAction::new(
+            "view_top",
+            "Top view",
+            |world, resources| set_ortho_view(world, resources, Some(OrthoView::Top)),
+        ))
+        .add_editor_action(EditorAction::new(
+            "toggle_orthographic",
+            "Toggle orthographic projection",
+            |world, resources| set_ortho_view(world, resources, None),
+        ))
+        .add_editor_action(EditorAction::new(
+            "frame_selected",
+            "Frame selected",
+            |world, resources| frame_widgets(world, resources, true),
+        ))
+        .add_editor_action(EditorAction::new(
+            "frame_all",
+            "Frame all",
+            |world, resources| frame_widgets(world, resources, false),
+        ))
+        .add_editor_action(EditorAction::new("translate", "Translate", |_, resources| {
+            resources
+                .get_mut::<EditorMode>()
+                .unwrap()
+                .set_transform(TransformMode::Translate);
+        }))
+        .add_editor_action(EditorAction::new("rotate", "Rotate", |_, resources| {
+            resources
+                .get_mut::<EditorMode>()
+                .unwrap()
+                .set_transform(TransformMode::Rotate);
+        }))
+        .add_editor_action(EditorAction::new("scale", "Scale", |_, resources| {
+            resources
+                .get_mut::<EditorMode>()
+                .unwrap()
+                .set_transform(TransformMode::Scale);
+        }))
+        .add_editor_action(EditorAction::new(
+            "cycle_space",
+            "Cycle transform space",
+            |_, resources| {
+                let mut mode = resources.get_mut::<EditorMode>().unwrap();
+                mode.space = mode.space.next();
+            },
+        ))
+        .add_editor_action(EditorAction::new(
+            "command_palette",
+            "Open command palette",
+            |_, resources| {
+                resources.get_mut::<CommandPalette>().unwrap().requested = true;
+            },
+        ))
+        .add_editor_action(EditorAction::new(
+            "save_as",
+            "Save prefab as",
+            |_, resources| {
+                let mut palette = resources.get_mut::<CommandPalette>().unwrap();
+                palette.requested = true;
+                palette.save_as = true;
+            },
+        ));
+}
+
+fn fly_cameras(world: &World) -> Vec<Entity> {
+    world
+        .query::<(Entity, &FlyCamera)>()
+        .iter()
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+/// Points the `FlyCamera`'s own yaw and pitch along `rotation`, so that it doesn't jump
+/// back to where it was looking once the mouse moves.
+fn sync_fly_camera(fly_camera: &mut FlyCamera, rotation: Quat) {
+    let (yaw, pitch) = look_angles(rotation * -Vec3::unit_z());
+    // `FlyCamera` keeps degrees and pitches around -X
+    fly_camera.yaw = yaw.to_degrees();
+    fly_camera.pitch = -pitch.to_degrees();
+}
+
+/// The orbit around whatever is `distance` in front of the camera.
+fn orbit_in_front(transform: &Transform, distance: f32) -> Orbit {
+    let eye = transform.translation();
+    let forward = transform.rotation() * -Vec3::unit_z();
+    Orbit::looking_at(eye, eye + forward * distance)
+}
+
+/// Turns the camera to an orthographic `view` of what it's orbiting or looking at, or
+/// without a `view` toggles the projection in place.
+fn set_ortho_view(world: &mut World, resources: &mut Resources, view: Option<OrthoView>) {
+    // numpad keys type values while transforming
+    if resources.get::<EditorMode>().unwrap().transform.is_some() {
+        return;
+    }
+    let mut editor_camera = resources.get_mut::<EditorCamera>().unwrap();
+    let view = match view {
+        Some(view) => view,
+        None => {
+            editor_camera.orthographic = !editor_camera.orthographic;
+            return;
+        }
+    };
+    editor_camera.orthographic = true;
+    for camera in fly_cameras(world) {
+        let transform = *world.get::<Transform>(camera).unwrap();
+        if !editor_camera.orbiting {
+            let distance = editor_camera.orbit.distance;
+            editor_camera.orbit = orbit_in_front(&transform, distance);
+        }
+        let (yaw, pitch) = view.angles();
+        editor_camera.orbit.yaw = yaw;
+        editor_camera.orbit.pitch = pitch;
+        let orbit = editor_camera.orbit;
+        editor_camera.tween = Some(CameraTween {
+            from: (transform.translation(), transform.rotation()),
+            to: (orbit.eye(), orbit.rotation()),
+            elapsed: 0.0,
+        });
+        // any mouse motion would turn a flying camera off the axis
+        world.get_mut::<FlyCamera>(camera).unwrap().enabled = false;
+    }
+}
+
+/// Moves the camera to fit the selected widgets, or all of them, keeping its direction.
+///
+/// Widgets are bounded by their mesh, or by their position if they don't have one.
+fn frame_widgets(world: &World, resources: &Resources, selected_only: bool) {
+    let meshes = resources.get::<Assets<Mesh>>().unwrap();
+    let mut bounds: Option<Aabb> = None;
+    for (_, selected, transform, mesh) in &mut world
+        .query::<(&Widget, &Selected, &GlobalTransform, Option<&Handle<Mesh>>)>()
+        .iter()
+    {
+        if selected_only && !selected.0 {
+            continue;
+        }
+        let widget_bounds = mesh
+            .and_then(|mesh| meshes.get(mesh))
+            .and_then(Aabb::from_mesh)
+            .map(|aabb| aabb.transformed(transform.value()))
+            .unwrap_or_else(|| Aabb::point(transform.translation()));
+        bounds = Some(match bounds {
+            Some(bounds) => bounds.merge(&widget_bounds),
+            None => widget_bounds,
+        });
+    }
+    let bounds = match bounds {
+        Some(bounds) => bounds,
+        None => return,
+    };
+
+    let mut editor_camera = resources.get_mut::<EditorCamera>().unwrap();
+    for (_, transform, projection) in &mut world
+        .query::<(&FlyCamera, &Transform, &PerspectiveProjection)>()
+        .iter()
+    {
+        let eye = transform.translation();
+        let forward = transform.rotation() * -Vec3::unit_z();
+        let radius = bounds.radius().max(MIN_FRAME_RADIUS);
+        let distance = frame_distance(radius, projection.fov, projection.aspect_ratio);
+        let center = bounds.center();
+        let orbit = Orbit::looking_at(center - forward * distance, center);
+        editor_camera.orbit = orbit;
+        editor_camera.ortho_height = frame_half_height(radius, projection.aspect_ratio);
+        editor_camera.tween = Some(CameraTween {
+            from: (eye, transform.rotation()),
+            to: (orbit.eye(), orbit.rotation()),
+            elapsed: 0.0,
+        });
+    }
+}
+
+/// Keeps a "Spawn" action for every bundle and an "Add" action for every component.
+fn library_actions_system(
+    mut shown: Local<(Vec<String>, Vec<String>)>,
+    default_bundles: Res<DefaultBundles>,
+    default_properties: Res<DefaultProperties>,
+    mut actions: ResMut<EditorActions>,
+) {
+    let bundles = default_bundles
+        .search("")
+        .into_iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    let components = default_properties
+        .search("")
+        .into_iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    if shown.0 == bundles && shown.1 == components {
+        return;
+    }
+
+    actions.remove_prefixed("spawn_bundle:");
+    for name in &bundles {
+        let bundle = name.clone();
+        actions.add(EditorAction::new(
+            format!("spawn_bundle:{}", name),
+            format!("Spawn {}", name),
+            move |world, resources| {
+                let camera = world
+                    .query::<(&FlyCamera, &GlobalTransform)>()
+                    .iter()
+                    .map(|(_, transform)| *transform)
+                    .next();
+                spawn_bundle(
+                    &bundle,
+                    &resources.get::<DefaultBundles>().unwrap(),
+                    &resources.get::<TypeRegistry>().unwrap(),
+                    &mut resources.get_mut::<EditorCommands>().unwrap(),
+                    camera.as_ref(),
+                );
+            },
+        ));
+    }
+
+    actions.remove_prefixed("add_component:");
+    for name in &components {
+        let component = name.clone();
+        actions.add(EditorAction::new(
+            format!("add_component:{}", name),
+            format!("Add {}", name),
+            move |world, resources| {
+                add_component(
+                    &component,
+                    &resources.get::<DefaultProperties>().unwrap(),
+                    &mut resources.get_mut::<EditorCommands>().unwrap(),
+                    &selected_widgets(world),
+                );
+            },
+        ));
+    }
+
+    *shown = (bundles, components);
+}
+
+/// Queues the actions whose keys were pressed.
+fn action_shortcut_system(
+    input: Res<Input<KeyCode>>,
+    keymap: Res<Keymap>,
+    focus: Res<TextFocus>,
+    mut actions: ResMut<EditorActions>,
+) {
+    let triggered = actions
+        .iter()
+        .filter(|action| keymap.just_pressed(&action.id, &input, &focus))
+        .map(|action| action.id.clone())
+        .collect::<Vec<_>>();
+    for id in triggered {
+        actions.queue(id);
+    }
+}
+
+/// Warns in the status bar about the keymap's actions that nothing handles, once for each
+/// set of bound actions, so that typos and removed actions don't silently do nothing.
+fn keymap_check_system(
+    mut checked: Local<Vec<String>>,
+    keymap: Res<Keymap>,
+    actions: Res<EditorActions>,
+    mut editor: ResMut<Editor>,
+) {
+    let bound = keymap
+        .actions()
+        .into_iter()
+        .map(|(action, _)| action)
+        .collect::<Vec<_>>();
+    if *checked == bound {
+        return;
+    }
+    let unknown = keymap.unknown_actions(|id| actions.get(id).is_some());
+    if !unknown.is_empty() {
+        editor.notice = Some(format!("unknown keymap actions: {}", unknown.join(", ")));
+    }
+    *checked = bound;
+}
+
+fn command_palette_system(
+    mut commands: Commands,
+    mut shown: Local<Autocomplete>,
+    input: Res<Input<KeyCode>>,
+    keymap: Res<Keymap>,
+    focus: Res<TextFocus>,
+    font: Res<EditorFont>,
+    editor: Res<Editor>,
+    mut actions: ResMut<EditorActions>,
+    mut palette: ResMut<CommandPalette>,
+    mut buttons: Query<(&ButtonFunction, Mut<ButtonToggled>, Mut<TextField>)>,
+    mut roots: Query<With<CommandPaletteRoot, Mut<Style>>>,
+    mut lists: Query<With<CommandPaletteList, (Entity, Option<&Children>)>>,
+) {
+    for (function, mut toggled, mut field) in &mut buttons.iter() {
+        if !matches!(function, ButtonFunction::CommandPalette) {
+            continue;
+        }
+
+        if palette.requested {
+            palette.requested = false;
+            palette.open = true;
+            palette.list.error = None;
+            toggled.0 = true;
+            if palette.save_as {
+                field.set(editor.path.to_string_lossy().into_owned());
+            } else {
+                field.set("");
+            }
+        } else if palette.open && !toggled.0 {
+            palette.open = false;
+            if palette.save_as {
+                palette.save_as = false;
+                if !field.cancelled && !field.text().is_empty() {
+                    palette.save_path = Some(PathBuf::from(field.text()));
+                }
+            } else if !field.cancelled {
+                if let Some(id) = palette.selected() {
+                    actions.queue(id);
+                }
+            }
+            palette.list.clear();
+        }
+
+        if palette.open && !palette.save_as {
+            let matches = actions.search(field.text());
+            palette.update(matches, &keymap);
+            if keymap.just_pressed("field_up", &input, &focus) {
+                palette.list.up();
+            }
+            if keymap.just_pressed("field_down", &input, &focus) {
+                palette.list.down();
+            }
+        }
+    }
+
+    for mut style in &mut roots.iter() {
+        let display = if palette.open {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if style.display != display {
+            style.display = display;
+        }
+    }
+
+    if *shown == palette.list {
+        return;
+    }
+    *shown = palette.list.clone();
+    for (list, children) in &mut lists.iter() {
+        if let Some(children) = children {
+            for &child in children.iter() {
+                commands.despawn_recursive(child);
+            }
+        }
+        let rows = spawn_autocomplete_rows(&mut commands, font.0, &palette.list);
+        commands.push_children(list, &rows);
+    }
+}
+
+fn spawn_inspector_row(
+    commands: &mut Commands,
+    font: Handle<Font>,
+    row: &InspectorRow,
+    entity: u32,
+    component: usize,
+) -> Entity {
+    let label = format!("{}{}", "  ".repeat(row.depth), row.label);
+    let value = match &row.value {
+        Some(value) if row.editable => value.clone(),
+        Some(value) => {
+            return spawn_text(
+                commands,
+                font,
+                format!("{}: {}", label, value),
+                Color::rgb(0.6, 0.6, 0.6),
+            )
+        }
+        None => return spawn_text(commands, font, label, Color::rgb(0.8, 0.8, 0.8)),
+    };
+
+    let field = InspectorField {
+        entity,
+        component,
+        path: row.path.clone(),
+        label: format!("{}: ", label),
+        value,
+    };
+    commands
+        .spawn(ButtonComponents {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Px(20.0)),
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: BUTTON_NONE_MATERIAL,
+            ..Default::default()
+        })
+        .with(ButtonFunction::EditProperty)
+        .with_children(|parent| {
+            parent.spawn(TextComponents {
+                text: Text {
+                    value: format!("{}{}", field.label, field.value),
+                    font,
+                    style: TextStyle {
+                        font_size: 16.0,
+                        color: Color::rgb(0.8, 0.8, 0.8),
+                    },
+                },
+                ..Default::default()
+            });
+        })
+        .with(field);
+    if !row.toggle {
+        commands
+            .with(ButtonToggled::default())
+            .with(TextField::default());
+    }
+    commands.current_entity().unwrap()
+}
+
+fn spawn_text(commands: &mut Commands, font: Handle<Font>, value: String, color: Color) -> Entity {
+    commands.spawn(TextComponents {
+        text: Text {
+            value,
+            font,
+            style: TextStyle {
+                font_size: 16.0,
+                color,
+            },
+        },
+        ..Default::default()
+    });
+    commands.current_entity().unwrap()
+}
+
+fn dependency_panel_system(
+    mut commands: Commands,
+    mut reader: Local<EventReader<AssetEvent<Scene>>>,
+    events: Res<Events<AssetEvent<Scene>>>,
+    editor: Res<Editor>,
+    root: Res<AssetRoot>,
+    font: Res<EditorFont>,
+    assets: Res<Assets<Scene>>,
+    mut panels: Query<With<DependencyPanel, (Entity, Option<&Children>)>>,
+) {
+    let mut changed = false;
+    for event in reader.iter(&events) {
+        match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                changed |= *handle == editor.scene;
+            }
+            AssetEvent::Removed { .. } => {}
+        }
+    }
+    if !changed {
+        return;
+    }
+
+    let scene = match assets.get(&editor.scene) {
+        Some(scene) => scene,
+        None => return,
+    };
+    let manifest = Manifest::from_scene(scene, &editor.path, &root.0);
+
+    for (panel, children) in &mut panels.iter() {
+        if let Some(children) = children {
+            for &child in children.iter() {
+                commands.despawn_recursive(child);
+            }
+        }
+
+        let mut rows = vec![spawn_text(
+            &mut commands,
+            font.0,
+            "Dependencies".to_string(),
+            Color::rgb(0.8, 0.8, 0.8),
+        )];
+        for dependency in &manifest.dependencies {
+            let row = if dependency.exists() {
+                spawn_text(
+                    &mut commands,
+                    font.0,
+                    format!("{}: {}", dependency.asset_type, dependency.path),
+                    Color::rgb(0.6, 0.6, 0.6),
+                )
+            } else {
+                spawn_text(
+                    &mut commands,
+                    font.0,
+                    format!("{}: {} (missing)", dependency.asset_type, dependency.path),
+                    Color::rgb(1.0, 0.8, 0.0),
+                )
+            };
+            rows.push(row);
+        }
+        commands.push_children(panel, &rows);
+    }
+}
+
+/// Captures the selection when a transform starts, and either writes the result to the
+/// scene when it ends or restores the captured transforms when it's cancelled.
+fn transform_session_system(
+    input: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    keymap: Res<Keymap>,
+    focus: Res<TextFocus>,
+    mut session: ResMut<TransformSession>,
+    mut mode: ResMut<EditorMode>,
+    mut gizmo: ResMut<GizmoState>,
+    mut editor: ResMut<EditorCommands>,
+    mut widgets: Query<(Entity, &Widget, &Selected, Mut<Transform>)>,
+) {
+    let active = mode.transform.is_some() || gizmo.drag.is_some();
+    let originals = match session.originals.take() {
+        Some(originals) => originals,
+        None => {
+            if active {
+                let mut originals = vec![];
+                for (entity, _, selected, transform) in &mut widgets.iter() {
+                    if selected.0 {
+                        originals.push((entity, *transform));
+                    }
+                }
+                session.originals = Some(originals);
+            }
+            return;
+        }
+    };
+
+    if !active {
+        for (entity, original) in originals {
+            if let (Ok(widget), Ok(transform)) = (
+                widgets.get::<Widget>(entity),
+                widgets.get::<Transform>(entity),
+            ) {
+                if transform.value() != original.value() {
+                    editor.insert_one(widget.0, transform.to_dynamic());
+                }
+            }
+        }
+    } else if keymap.just_pressed("cancel", &input, &focus)
+        || mouse.just_pressed(MouseButton::Right)
+    {
+        for (entity, original) in originals {
+            if let Ok(mut transform) = widgets.get_mut::<Transform>(entity) {
+                *transform = original;
+            }
+        }
+        mode.end_transform();
+        gizmo.drag = None;
+    } else {
+        // entities selected during the transform are transformed from here on
+        let mut originals = originals;
+        for (entity, _, selected, transform) in &mut widgets.iter() {
+            if selected.0 && originals.iter().all(|(original, _)| *original != entity) {
+                originals.push((entity, *transform));
+            }
+        }
+        session.originals = Some(originals);
+    }
+}
+
+/// Moves the gizmo to the first selected widget, turns it to the `TransformSpace` and shows
+/// the handles of the current `TransformMode`.
+fn gizmo_follow_system(
+    mode: Res<EditorMode>,
+    editor_camera: Res<EditorCamera>,
+    mut widgets: Query<With<Widget, (&Selected, &GlobalTransform)>>,
+    mut cameras: Query<With<FlyCamera, &GlobalTransform>>,
+    mut projections: Query<With<FlyCamera, &PerspectiveProjection>>,
+    mut gizmos: Query<With<Gizmo, Mut<Transform>>>,
+    mut handles: Query<(&GizmoHandle, Mut<Draw>)>,
+) {
+    let mut pivot = None;
+    for (selected, transform) in &mut widgets.iter() {
+        if selected.0 {
+            pivot = Some(transform.translation());
+            break;
+        }
+    }
+
+    if let Some(pivot) = pivot {
+        let mut distance = 1.0;
+        for camera in &mut cameras.iter() {
+            distance = (camera.translation() - pivot).length();
+        }
+        if editor_camera.orthographic {
+            // the distance a perspective camera would see as much from
+            for projection in &mut projections.iter() {
+                distance = editor_camera.ortho_height / (projection.fov * 0.5).tan();
+            }
+        }
+        let scale = Vec3::splat(distance * GIZMO_SCALE);
+        let rotation = space_basis(mode.space, &mut widgets, &mut cameras);
+        for mut transform in &mut gizmos.iter() {
+            *transform = Transform::new(Mat4::from_scale_rotation_translation(
+                scale,
+                rotation,
+                pivot,
+            ));
+        }
+    }
+
+    let shown = mode.transform.unwrap_or(TransformMode::Translate);
+    for (handle, mut draw) in &mut handles.iter() {
+        let visible = pivot.is_some() && handle.mode == shown;
+        if draw.is_visible != visible {
+            draw.is_visible = visible;
+        }
+    }
+}
+
+#[derive(Default)]
+struct GizmoCursor {
+    reader: EventReader<CursorMoved>,
+    position: Option<Vec2>,
+}
+
+/// Drags the gizmo handle under the cursor, sending the same `EditorEvent`s as the keyboard.
+fn gizmo_drag_system(
+    mut cursor: Local<GizmoCursor>,
+    mouse: Res<Input<MouseButton>>,
+    cursor_moved: Res<Events<CursorMoved>>,
+    windows: Res<Windows>,
+    pick_state: Res<PickState>,
+    editor_camera: Res<EditorCamera>,
+    meshes: Res<Assets<Mesh>>,
+    mut gizmo: ResMut<GizmoState>,
+    mut events: ResMut<Events<EditorEvent>>,
+    mut cameras: Query<With<FlyCamera, (&Camera, &GlobalTransform)>>,
+    mut handles: Query<(Entity, &GizmoHandle, &Draw, &GlobalTransform, &Handle<Mesh>)>,
+    mut gizmos: Query<With<Gizmo, &GlobalTransform>>,
+) {
+    if let Some(moved) = cursor.reader.latest(&cursor_moved) {
+        cursor.position = Some(moved.position);
+    }
+    if mouse.just_released(MouseButton::Left) {
+        gizmo.drag = None;
+    }
+
+    let ray = match cursor_ray(cursor.position, &windows, &mut cameras) {
+        Some(ray) => ray,
+        None => return,
+    };
+
+    if mouse.just_pressed(MouseButton::Left) {
+        let picked = if editor_camera.orthographic {
+            ray_pick(
+                &ray,
+                &meshes,
+                (&mut handles.iter())
+                    .filter(|(_, _, draw, _, _)| draw.is_visible)
+                    .map(|(entity, _, _, transform, mesh)| (entity, transform, mesh)),
+            )
+        } else {
+            pick_state.top(Group::default()).map(|pick| pick.entity())
+        };
+        let handle = picked.and_then(|entity| {
+            match (handles.get::<GizmoHandle>(entity), handles.get::<Draw>(entity)) {
+                (Ok(handle), Ok(draw)) if draw.is_visible => Some(*handle),
+                _ => None,
+            }
+        });
+        let mut gizmo_transform = None;
+        for transform in &mut gizmos.iter() {
+            gizmo_transform = Some(*transform);
+        }
+        if let (Some(handle), Some(transform)) = (handle, gizmo_transform) {
+            let origin = transform.translation();
+            let axis = transform.rotation() * handle.axis.unit();
+            if let Some(start) = handle_position(handle.mode, &ray, origin, axis) {
+                gizmo.drag = Some(GizmoDrag {
+                    handle,
+                    origin,
+                    axis,
+                    start,
+                    last: start,
+                });
+            }
+        }
+        return;
+    }
 
-    if bundles_path.exists() {
-        let text = fs::read_to_string(bundles_path).unwrap();
-        let mut deserializer = ::ron::Deserializer::from_str(&text).unwrap();
-        let mut deserializer = erased_serde::Deserializer::erase(&mut deserializer);
-        let dynamic = DynamicProperties::deserialize(&mut deserializer, &property).unwrap();
-        *default_bundles =
-            DefaultBundles::from_dynamic(&dynamic.as_properties().unwrap().to_dynamic());
+    let drag = match &mut gizmo.drag {
+        Some(drag) => drag,
+        None => return,
+    };
+    let current = match handle_position(drag.handle.mode, &ray, drag.origin, drag.axis) {
+        Some(current) => current,
+        None => return,
+    };
+    let event = match drag.handle.mode {
+        TransformMode::Translate => EditorEvent::Translate(drag.axis * (current - drag.last)),
+        TransformMode::Rotate => EditorEvent::Rotate(Quat::from_axis_angle(
+            drag.axis,
+            angle_delta(drag.last, current),
+        )),
+        TransformMode::Scale => {
+            // scale factors are along the entity's own axes
+            let factor =
+                scale_factor(drag.start, current) / scale_factor(drag.start, drag.last);
+            EditorEvent::Scale(Vec3::one() + drag.handle.axis.unit() * (factor - 1.0))
+        }
+    };
+    drag.last = current;
+    events.send(event);
+}
+
+/// The ray under the cursor from the editor camera.
+fn cursor_ray(
+    cursor: Option<Vec2>,
+    windows: &Windows,
+    cameras: &mut Query<With<FlyCamera, (&Camera, &GlobalTransform)>>,
+) -> Option<Ray> {
+    let window = windows.get_primary()?;
+    let window_size = Vec2::new(window.width as f32, window.height as f32);
+    let mut ray = None;
+    for (camera, camera_transform) in &mut cameras.iter() {
+        ray = cursor.and_then(|position| {
+            Ray::from_screen(
+                camera.projection_matrix,
+                *camera_transform.value(),
+                window_size,
+                position,
+            )
+        });
     }
+    ray
+}
+
+/// The nearest of `targets` whose mesh bounds `ray` hits.
+///
+/// Used in orthographic views, where `PickState` can't pick since it casts its rays from
+/// the camera's position.
+fn ray_pick<'a, I>(ray: &Ray, meshes: &Assets<Mesh>, targets: I) -> Option<Entity>
+where
+    I: Iterator<Item = (Entity, &'a GlobalTransform, &'a Handle<Mesh>)>,
+{
+    targets
+        .filter_map(|(entity, transform, mesh)| {
+            let bounds = Aabb::from_mesh(meshes.get(mesh)?)?;
+            let distance = bounds.ray_hit(ray, transform.value())?;
+            // degenerate transforms and meshes can't be hit
+            if distance.is_finite() {
+                Some((distance, entity))
+            } else {
+                None
+            }
+        })
+        .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        .map(|(_, entity)| entity)
+}
 
-    if properties_path.exists() {
-        let text = fs::read_to_string(properties_path).unwrap();
-        let mut deserializer = ::ron::Deserializer::from_str(&text).unwrap();
-        let mut deserializer = erased_serde::Deserializer::erase(&mut deserializer);
-        let dynamic = DynamicProperties::deserialize(&mut deserializer, &property).unwrap();
-        *default_properties =
-            DefaultProperties::from_dynamic(&dynamic.as_properties().unwrap().to_dynamic());
+/// Where the cursor is along a handle's world-space `axis`, or its angle around it.
+fn handle_position(mode: TransformMode, ray: &Ray, origin: Vec3, axis: Vec3) -> Option<f32> {
+    match mode {
+        TransformMode::Translate | TransformMode::Scale => axis_position(ray, origin, axis),
+        TransformMode::Rotate => ring_angle(ray, origin, axis),
     }
 }
 
-fn setup_thread_local(world: &mut World, resources: &mut Resources) {
-    let mut editor = resources.get_mut::<Editor>().unwrap();
-    editor.read("assets/prefab.scn", world, resources).unwrap();
+#[derive(Default)]
+struct EditorCameraState {
+    motion: EventReader<MouseMotion>,
+    wheel: EventReader<MouseWheel>,
 }
 
-fn camera_system(input: Res<Input<KeyCode>>, mut query: Query<Mut<FlyCamera>>) {
-    if input.just_pressed(KeyCode::Q) {
-        for mut fly_camera in &mut query.iter() {
-            fly_camera.enabled = !fly_camera.enabled;
+/// Moves the camera along the `EditorCamera`'s tween, or around its orbit.
+fn editor_camera_system(
+    mut state: Local<EditorCameraState>,
+    time: Res<Time>,
+    mode: Res<EditorMode>,
+    mouse: Res<Input<MouseButton>>,
+    motion: Res<Events<MouseMotion>>,
+    wheel: Res<Events<MouseWheel>>,
+    windows: Res<Windows>,
+    mut editor_camera: ResMut<EditorCamera>,
+    mut cameras: Query<(Mut<FlyCamera>, Mut<Transform>)>,
+) {
+    let mut drag = Vec2::zero();
+    for motion in state.motion.iter(&motion) {
+        drag += motion.delta;
+    }
+    let mut zoom = 0.0;
+    for wheel in state.wheel.iter(&wheel) {
+        zoom += match wheel.unit {
+            MouseScrollUnit::Line => wheel.y,
+            MouseScrollUnit::Pixel => wheel.y / 12.0,
+        };
+    }
+
+    if editor_camera.orbiting && mouse.pressed(MouseButton::Middle) {
+        editor_camera
+            .orbit
+            .turn(-drag.x() * ORBIT_SENSITIVITY, -drag.y() * ORBIT_SENSITIVITY);
+    }
+    // in mouse mode the wheel adjusts `EditorMode::multiplier` instead
+    if !mode.mouse {
+        let factor = 0.9f32.powf(zoom);
+        if editor_camera.orthographic {
+            editor_camera.ortho_height *= factor;
+        } else if editor_camera.orbiting {
+            editor_camera.orbit.distance *= factor;
         }
     }
-}
 
-fn button_system(
-    mut query: Query<
-        With<
-            Button,
+    let orbit = editor_camera.orbit;
+    let orbiting = editor_camera.orbiting;
+    if editor_camera.orthographic
+        && !orbiting
+        && editor_camera.tween.is_none()
+        && mouse.pressed(MouseButton::Middle)
+    {
+        // pan so that what's under the cursor stays under it
+        let units = windows.get_primary().map_or(0.0, |window| {
+            2.0 * editor_camera.ortho_height / window.height as f32
+        });
+        for (_, mut transform) in &mut cameras.iter() {
+            let rotation = transform.rotation();
+            let translation =
+                transform.translation() + rotation * Vec3::new(-drag.x(), drag.y(), 0.0) * units;
+            *transform = Transform::new(Mat4::from_rotation_translation(rotation, translation));
+        }
+    }
+
+    let (translation, rotation, done) = match &mut editor_camera.tween {
+        Some(tween) => {
+            tween.elapsed += time.delta_seconds;
+            let t = ease_out(tween.elapsed / CAMERA_TWEEN_SECONDS);
             (
-                Mutated<Interaction>,
-                Option<Mut<ButtonToggled>>,
-                Mut<Handle<ColorMaterial>>,
-                &ButtonFunction,
-            ),
-        >,
-    >,
-) {
-    for (interaction, mut toggled, mut material, _) in &mut query.iter() {
-        match *interaction {
-            Interaction::Clicked => {
-                if let Some(toggled) = &mut toggled {
-                    toggled.0 = !toggled.0;
-                    if toggled.0 {
-                        *material = BUTTON_TOGGLED_MATERIAL
-                    } else {
-                        *material = BUTTON_CLICKED_MATERIAL
-                    }
-                } else {
-                    *material = BUTTON_CLICKED_MATERIAL
-                }
-            }
-            Interaction::Hovered => *material = BUTTON_HOVERED_MATERIAL,
-            Interaction::None => *material = BUTTON_NONE_MATERIAL,
+                tween.from.0.lerp(tween.to.0, t),
+                tween.from.1.lerp(tween.to.1, t),
+                tween.elapsed >= CAMERA_TWEEN_SECONDS,
+            )
+        }
+        None if orbiting => (orbit.eye(), orbit.rotation(), false),
+        None => return,
+    };
+    if done {
+        editor_camera.tween = None;
+    }
+
+    for (mut fly_camera, mut transform) in &mut cameras.iter() {
+        *transform = Transform::new(Mat4::from_rotation_translation(rotation, translation));
+        if done {
+            sync_fly_camera(&mut fly_camera, rotation);
         }
     }
 }
 
-fn button_enter_system(
-    input: Res<Input<KeyCode>>,
-    mut query: Query<With<Button, (Mut<ButtonToggled>, &ButtonFunction)>>,
+/// Gives the editor camera an orthographic projection in orthographic views.
+///
+/// bevy only updates `Camera::projection_matrix` from the `PerspectiveProjection`, which is
+/// kept for its aspect ratio and to switch back to.
+fn camera_projection_system(
+    editor_camera: Res<EditorCamera>,
+    mut cameras: Query<With<FlyCamera, (Mut<Camera>, &PerspectiveProjection)>>,
 ) {
-    for (mut toggled, _) in &mut query.iter() {
-        if toggled.0 {
-            if input.just_pressed(KeyCode::Return) {
-                toggled.0 = false;
-            }
+    for (mut camera, perspective) in &mut cameras.iter() {
+        let projection = if editor_camera.orthographic {
+            orthographic_projection(
+                editor_camera.ortho_height,
+                perspective.aspect_ratio,
+                ORTHO_DEPTH,
+            )
+        } else {
+            perspective.get_projection_matrix()
+        };
+        if camera.projection_matrix != projection {
+            camera.projection_matrix = projection;
         }
     }
 }
 
-fn text_button_system(
+fn status_bar_system(
+    mode: Res<EditorMode>,
     input: Res<Input<KeyCode>>,
-    default_properties: Res<DefaultProperties>,
-    mut editor: ResMut<EditorCommands>,
-    mut query: Query<With<Button, (&ButtonToggled, &ButtonFunction, &Children)>>,
-    mut mutated: Query<With<Button, (Mutated<ButtonToggled>, &ButtonFunction, &Children)>>,
-    texts: Query<Mut<Text>>,
-    mut selected: Query<(&Widget, &SelectablePickMesh)>,
+    keymap: Res<Keymap>,
+    focus: Res<TextFocus>,
+    settings: Res<EditorSettings>,
+    editor: Res<Editor>,
+    editor_camera: Res<EditorCamera>,
+    mut cameras: Query<&FlyCamera>,
+    mut selected: Query<&Selected>,
+    mut status_bars: Query<With<StatusBar, Mut<Text>>>,
 ) {
-    for (toggled, function, children) in &mut mutated.iter() {
-        if toggled.0 {
-            match function {
-                ButtonFunction::Save => {}
-                ButtonFunction::AddComponent => {
-                    for &child in children.iter() {
-                        texts.get_mut::<Text>(child).unwrap().value.clear();
-                    }
-                }
-            }
-        } else {
-            match function {
-                ButtonFunction::Save => {}
-                ButtonFunction::AddComponent => {
-                    for &child in children.iter() {
-                        let mut component_name = "Add component".to_string();
-                        mem::swap(
-                            &mut texts.get_mut::<Text>(child).unwrap().value,
-                            &mut component_name,
-                        );
-                        for (widget, selected) in &mut selected.iter() {
-                            if selected.selected() {
-                                let component = default_properties.get(&component_name).unwrap();
-                                editor.insert_one(widget.0, component);
-                                editor.sync_one_to_world(widget.0, component_name.clone());
-                            }
-                        }
-                    }
-                }
-            }
+    let mut camera = if editor_camera.orbiting {
+        "orbit"
+    } else {
+        "locked"
+    };
+    for fly_camera in &mut cameras.iter() {
+        if fly_camera.enabled {
+            camera = "fly";
+        }
+    }
+    let projection = if editor_camera.orthographic {
+        "orthographic"
+    } else {
+        "perspective"
+    };
+    let mut selection = 0;
+    for selected in &mut selected.iter() {
+        if selected.0 {
+            selection += 1;
         }
     }
 
-    for (toggled, function, children) in &mut query.iter() {
-        if toggled.0 {
-            match function {
-                ButtonFunction::Save => {}
-                ButtonFunction::AddComponent => {
-                    let mut text = String::new();
-                    for keycode in input.get_just_pressed() {
-                        text.push_str(keycode.display().as_str());
-                    }
-                    if input.pressed(KeyCode::LShift) {
-                        text.make_ascii_uppercase()
-                    }
-                    for &child in children.iter() {
-                        texts.get_mut::<Text>(child).unwrap().value.push_str(&text);
-                    }
-                    if input.just_pressed(KeyCode::Back) {
-                        for &child in children.iter() {
-                            texts.get_mut::<Text>(child).unwrap().value.pop();
-                        }
-                    }
-                }
-            }
+    let mut status = format!(
+        "{}    |    snap {}    |    camera {}, {}    |    {} selected",
+        mode.status(),
+        if snapping(&settings, &keymap, &input, &focus) {
+            "on"
+        } else {
+            "off"
+        },
+        camera,
+        projection,
+        selection,
+    );
+    if let Some(notice) = &editor.notice {
+        status.push_str(&format!("    |    {}", notice));
+    }
+    for mut text in &mut status_bars.iter() {
+        if text.value != status {
+            text.value = status.clone();
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum TransformMode {
-    Translate,
-    Rotate,
-    Scale,
-}
+fn warning_badge_system(
+    mut commands: Commands,
+    font: Res<EditorFont>,
+    windows: Res<Windows>,
+    mut editor: ResMut<Editor>,
+    mut failed: Query<With<Widget, (Entity, Added<AssetLoadFailed>)>>,
+    widgets: Query<With<AssetLoadFailed, &GlobalTransform>>,
+    mut cameras: Query<With<FlyCamera, (&Camera, &GlobalTransform)>>,
+    mut badges: Query<(Entity, &WarningBadge, Mut<Style>)>,
+) {
+    for (entity, failed) in &mut failed.iter() {
+        editor.notice = Some(format!("failed to load {}: {}", failed.path, failed.error));
+        commands
+            .spawn(TextComponents {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    ..Default::default()
+                },
+                text: Text {
+                    value: "!".to_string(),
+                    font: font.0,
+                    style: TextStyle {
+                        font_size: 30.0,
+                        color: Color::rgb(1.0, 0.8, 0.0),
+                    },
+                },
+                ..Default::default()
+            })
+            .with(WarningBadge(entity));
+    }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Axis {
-    X,
-    Y,
-    Z,
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let window_size = Vec2::new(window.width as f32, window.height as f32);
+
+    for (camera, camera_transform) in &mut cameras.iter() {
+        for (badge, &WarningBadge(widget), mut style) in &mut badges.iter() {
+            let translation = match widgets.get::<GlobalTransform>(widget) {
+                Ok(transform) => transform.translation(),
+                Err(_) => {
+                    commands.despawn(badge);
+                    continue;
+                }
+            };
+            match world_to_screen(camera, camera_transform, window_size, translation) {
+                Some(position) => {
+                    style.display = Display::Flex;
+                    // ui layout has y pointing up, so `top` is measured from the bottom
+                    style.position = Rect {
+                        left: Val::Px(position.x()),
+                        top: Val::Px(position.y()),
+                        ..Default::default()
+                    };
+                }
+                None => style.display = Display::None,
+            }
+        }
+    }
 }
 
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct EditorMode {
     mouse: bool,
     transform: Option<TransformMode>,
-    axis: Option<Axis>,
-    decimal: Option<i32>,
+    constraint: Constraint,
+    /// How far the selection has been moved, rotated or scaled by the current input.
     value: f32,
+    /// Value typed while transforming, parsed with `parse_numeric`.
+    entry: String,
+    /// Why `entry` can't be applied, if it can't.
+    entry_error: Option<String>,
+    /// Scales mouse motion in mouse mode, adjusted with the wheel.
+    multiplier: f32,
+    space: TransformSpace,
+}
+
+impl Default for EditorMode {
+    fn default() -> Self {
+        Self {
+            mouse: false,
+            transform: None,
+            constraint: Constraint::default(),
+            value: 0.0,
+            entry: String::new(),
+            entry_error: None,
+            multiplier: 0.001,
+            space: TransformSpace::Global,
+        }
+    }
+}
+
+impl EditorMode {
+    /// A summary of the mode for the status bar, e.g. `Translate X 1.25`.
+    fn status(&self) -> String {
+        let mut status = match self.transform {
+            Some(TransformMode::Translate) => "Translate".to_string(),
+            Some(TransformMode::Rotate) => "Rotate".to_string(),
+            Some(TransformMode::Scale) => "Scale".to_string(),
+            None => return "Select".to_string(),
+        };
+        match self.constraint.axis {
+            Some(axis) if self.constraint.plane => {
+                let [first, second] = axis.others();
+                status.push_str(&format!(" {:?}{:?}", first, second));
+            }
+            Some(axis) => status.push_str(&format!(" {:?}", axis)),
+            None => status.push_str(" (no axis)"),
+        }
+        status.push_str(&format!(" {:?}", self.space).to_lowercase());
+        let value = if self.transform == Some(TransformMode::Rotate) {
+            format!("{:.2}deg", self.value.to_degrees())
+        } else {
+            format!("{:.3}", self.value)
+        };
+        if self.mouse {
+            status.push_str(&format!("  {}  mouse x{:.3}", value, self.multiplier));
+        } else if !self.entry.is_empty() {
+            status.push_str(&format!("  {}  = {}", self.entry, value));
+            if let Some(error) = &self.entry_error {
+                status.push_str(&format!("  ({})", error));
+            }
+        } else {
+            status.push_str(&format!("  {}", value));
+        }
+        status
+    }
+
+    /// The `value` of a transform that hasn't changed anything yet.
+    fn identity(&self) -> f32 {
+        match self.transform {
+            Some(TransformMode::Scale) => 1.0,
+            _ => 0.0,
+        }
+    }
+
+    /// The event moving the selection by `delta`, see `transform_event`.
+    fn event(&self, basis: Quat, delta: f32) -> Option<EditorEvent> {
+        transform_event(self.transform?, self.constraint, basis, delta)
+    }
+
+    /// The `value` that `entry` asks for, see `entry_target`.
+    fn entry_target(&self, basis: Quat, origin: (Vec3, Vec3)) -> Result<f32, String> {
+        let transform = self
+            .transform
+            .ok_or_else(|| "nothing to transform".to_string())?;
+        entry_target(&self.entry, transform, self.constraint, basis, origin)
+    }
+
+    /// Ends the current transform, leaving `TransformSession` to commit or restore it.
+    fn end_transform(&mut self) {
+        self.mouse = false;
+        self.transform = None;
+        self.constraint = Constraint::default();
+        self.value = 0.0;
+        self.entry.clear();
+        self.entry_error = None;
+    }
+
+    /// Switches to `transform`, or toggles mouse input if it's already active.
+    fn set_transform(&mut self, transform: TransformMode) {
+        if self.transform == Some(transform) {
+            self.mouse = !self.mouse;
+        } else {
+            self.transform = Some(transform);
+            self.value = self.identity();
+        }
+    }
 }
 
 struct InputSystem {
-    multiplier: f32,
     motion: EventReader<MouseMotion>,
     wheel: EventReader<MouseWheel>,
+    characters: EventReader<ReceivedCharacter>,
+    /// Translation and scale of the selection when the typed value was started.
+    entry_origin: (Vec3, Vec3),
 }
 
 impl InputSystem {
@@ -586,9 +3418,10 @@ impl InputSystem {
 impl Default for InputSystem {
     fn default() -> Self {
         Self {
-            multiplier: 0.001,
             motion: Default::default(),
             wheel: Default::default(),
+            characters: Default::default(),
+            entry_origin: (Vec3::zero(), Vec3::one()),
         }
     }
 }
@@ -596,186 +3429,153 @@ impl Default for InputSystem {
 fn input_system(
     mut state: Local<InputSystem>,
     input: Res<Input<KeyCode>>,
+    keymap: Res<Keymap>,
+    focus: Res<TextFocus>,
     mut mode: ResMut<EditorMode>,
     mut events: ResMut<Events<EditorEvent>>,
     motion: Res<Events<MouseMotion>>,
     wheel: Res<Events<MouseWheel>>,
+    characters: Res<Events<ReceivedCharacter>>,
+    mut widgets: Query<With<Widget, (&Selected, &GlobalTransform)>>,
+    mut cameras: Query<With<FlyCamera, &GlobalTransform>>,
 ) {
-    if input.just_pressed(KeyCode::T) {
-        if mode.transform == Some(TransformMode::Translate) {
-            mode.mouse = !mode.mouse;
-        }
-        mode.transform = Some(TransformMode::Translate);
+    let typed = state
+        .characters
+        .iter(&characters)
+        .map(|event| event.char)
+        .filter(|c| !c.is_control())
+        .collect::<String>();
+    // a typed value takes the keyboard like a text field, see `text_field_system`
+    if focus.0 && mode.entry.is_empty() {
+        return;
     }
-    if input.just_pressed(KeyCode::R) {
-        if mode.transform == Some(TransformMode::Rotate) {
-            mode.mouse = !mode.mouse;
-        }
-        mode.transform = Some(TransformMode::Rotate);
+    if keymap.just_pressed("confirm", &input, &focus) {
+        mode.end_transform();
     }
-    if input.just_pressed(KeyCode::S) {
-        if mode.transform == Some(TransformMode::Scale) {
-            mode.mouse = !mode.mouse;
+    for &(action, plane_action, axis) in &[
+        ("axis_x", "plane_x", Axis::X),
+        ("axis_y", "plane_y", Axis::Y),
+        ("axis_z", "plane_z", Axis::Z),
+    ] {
+        if keymap.just_pressed(action, &input, &focus) {
+            mode.constraint = Constraint::axis(axis);
+        }
+        if keymap.just_pressed(plane_action, &input, &focus) {
+            mode.constraint = Constraint::plane(axis);
         }
-        mode.transform = Some(TransformMode::Scale);
-    }
-    if input.just_pressed(KeyCode::Return) {
-        mode.mouse = false;
-        mode.transform = None;
-        mode.axis = None;
-        mode.decimal = None;
-        mode.value = 0.0;
-    }
-    if input.just_pressed(KeyCode::X) {
-        mode.axis = Some(Axis::X);
-    }
-    if input.just_pressed(KeyCode::Y) {
-        mode.axis = Some(Axis::Y);
-    }
-    if input.just_pressed(KeyCode::Z) {
-        mode.axis = Some(Axis::Z);
-    }
-    if input.just_pressed(KeyCode::Period) {
-        mode.decimal = Some(0);
     }
 
+    let basis = space_basis(mode.space, &mut widgets, &mut cameras);
     let mouse_mode = mode.mouse;
 
     if mouse_mode {
-        let mut transform = |delta| {
-            mode.value += delta;
-            match mode.transform {
-                Some(TransformMode::Translate) => {
-                    let value = match mode.axis {
-                        Some(Axis::X) => Vec3::new(delta, 0.0, 0.0),
-                        Some(Axis::Y) => Vec3::new(0.0, delta, 0.0),
-                        Some(Axis::Z) => Vec3::new(0.0, 0.0, delta),
-                        None => Vec3::zero(),
-                    };
-                    let event = EditorEvent::Translate(value);
-                    events.send(event);
-                }
-                Some(TransformMode::Rotate) => {
-                    let value = match mode.axis {
-                        Some(Axis::X) => Quat::from_rotation_x(delta),
-                        Some(Axis::Y) => Quat::from_rotation_y(delta),
-                        Some(Axis::Z) => Quat::from_rotation_z(delta),
-                        None => Quat::identity(),
-                    };
-                    let event = EditorEvent::Rotate(value);
-                    events.send(event);
-                }
-                Some(TransformMode::Scale) => {
-                    let value = match mode.axis {
-                        Some(Axis::X) => Vec3::new(delta, 1.0, 1.0),
-                        Some(Axis::Y) => Vec3::new(1.0, delta, 1.0),
-                        Some(Axis::Z) => Vec3::new(1.0, 1.0, delta),
-                        None => Vec3::zero(),
-                    };
-                    let event = EditorEvent::Scale(value);
-                    events.send(event);
-                }
-                None => {}
-            }
-        };
-
         for wheel in state.wheel.iter(&wheel) {
             let delta = match wheel.unit {
                 MouseScrollUnit::Line => wheel.y * 0.003,
                 MouseScrollUnit::Pixel => wheel.y * 0.00025,
             };
-            state.multiplier += delta;
-            state.multiplier = state.multiplier.max(0.001);
+            mode.multiplier += delta;
+            mode.multiplier = mode.multiplier.max(0.001);
         }
 
         for motion in state.motion.iter(&motion) {
-            transform(motion.delta.x() * state.multiplier);
+            let delta = motion.delta.x() * mode.multiplier;
+            let change = if mode.transform == Some(TransformMode::Scale) {
+                let factor = mouse_scale_factor(mode.value, delta);
+                mode.value *= factor;
+                factor
+            } else {
+                mode.value += delta;
+                delta
+            };
+            if let Some(event) = mode.event(basis, change) {
+                events.send(event);
+            }
         }
-    } else {
-        let mut transform = |digit| {
-            let prev_value = mode.value;
-            match &mut mode.decimal {
-                Some(digits) => {
-                    *digits += 1;
-                    mode.value += 10.0_f32.powi(-*digits) * digit;
-                }
-                None => {
-                    mode.value *= 10.0;
-                    mode.value += digit;
-                }
-            }
-            let delta = mode.value - prev_value;
-            match mode.transform {
-                Some(TransformMode::Translate) => {
-                    let value = match mode.axis {
-                        Some(Axis::X) => Vec3::new(delta, 0.0, 0.0),
-                        Some(Axis::Y) => Vec3::new(0.0, delta, 0.0),
-                        Some(Axis::Z) => Vec3::new(0.0, 0.0, delta),
-                        None => Vec3::zero(),
-                    };
-                    let event = EditorEvent::Translate(value);
-                    events.send(event);
-                }
-                Some(TransformMode::Rotate) => {
-                    let value = match mode.axis {
-                        Some(Axis::X) => Quat::from_rotation_x(delta),
-                        Some(Axis::Y) => Quat::from_rotation_y(delta),
-                        Some(Axis::Z) => Quat::from_rotation_z(delta),
-                        None => Quat::identity(),
-                    };
-                    let event = EditorEvent::Rotate(value);
-                    events.send(event);
+        return;
+    }
+    if mode.transform.is_none() {
+        return;
+    }
+
+    let entry = mode.entry.clone();
+    for c in typed.chars() {
+        // letters are shortcuts until a value has been started
+        if mode.entry.is_empty() && !"0123456789.-+=(".contains(c) {
+            continue;
+        }
+        if mode.entry.is_empty() {
+            let mut origin = (Vec3::zero(), Vec3::one());
+            for (selected, transform) in &mut widgets.iter() {
+                if selected.0 {
+                    origin = (transform.translation(), transform.scale());
+                    break;
                 }
-                Some(TransformMode::Scale) => {
-                    let value = match mode.axis {
-                        Some(Axis::X) => Vec3::new(delta, 1.0, 1.0),
-                        Some(Axis::Y) => Vec3::new(1.0, delta, 1.0),
-                        Some(Axis::Z) => Vec3::new(1.0, 1.0, delta),
-                        None => Vec3::zero(),
-                    };
-                    let event = EditorEvent::Scale(value);
+            }
+            state.entry_origin = origin;
+            mode.value = mode.identity();
+        }
+        mode.entry.push(c);
+    }
+    if !mode.entry.is_empty() && input.just_pressed(KeyCode::Back) {
+        mode.entry.pop();
+    }
+    if !mode.entry.is_empty() && keymap.just_pressed("field_cancel", &input, &focus) {
+        mode.entry.clear();
+    }
+
+    if mode.entry != entry {
+        let target = if mode.entry.is_empty() {
+            Ok(mode.identity())
+        } else {
+            mode.entry_target(basis, state.entry_origin)
+        };
+        match target {
+            Ok(target) => {
+                mode.entry_error = None;
+                let change = match mode.transform {
+                    Some(TransformMode::Scale) => target / mode.value,
+                    _ => target - mode.value,
+                };
+                mode.value = target;
+                if let Some(event) = mode.event(basis, change) {
                     events.send(event);
                 }
-                None => {}
             }
-        };
-
-        if input.just_pressed(KeyCode::Key1) {
-            transform(1.0);
-        }
-        if input.just_pressed(KeyCode::Key2) {
-            transform(2.0);
-        }
-        if input.just_pressed(KeyCode::Key3) {
-            transform(3.0);
-        }
-        if input.just_pressed(KeyCode::Key4) {
-            transform(4.0);
+            Err(err) => mode.entry_error = Some(err),
         }
-        if input.just_pressed(KeyCode::Key5) {
-            transform(5.0);
-        }
-        if input.just_pressed(KeyCode::Key6) {
-            transform(6.0);
-        }
-        if input.just_pressed(KeyCode::Key7) {
-            transform(7.0);
-        }
-        if input.just_pressed(KeyCode::Key8) {
-            transform(8.0);
-        }
-        if input.just_pressed(KeyCode::Key9) {
-            transform(9.0);
-        }
-        if input.just_pressed(KeyCode::Key0) {
-            transform(0.0);
+    }
+
+    if !entry.is_empty() && keymap.just_pressed("field_confirm", &input, &focus) {
+        mode.end_transform();
+    }
+}
+
+/// Rotation taking the global axes to the axes of `space`, for the first selected widget
+/// and the camera.
+fn space_basis(
+    space: TransformSpace,
+    widgets: &mut Query<With<Widget, (&Selected, &GlobalTransform)>>,
+    cameras: &mut Query<With<FlyCamera, &GlobalTransform>>,
+) -> Quat {
+    let mut entity = Quat::identity();
+    for (selected, transform) in &mut widgets.iter() {
+        if selected.0 {
+            entity = transform.rotation();
+            break;
         }
     }
+    let mut camera = Quat::identity();
+    for transform in &mut cameras.iter() {
+        camera = transform.rotation();
+    }
+    space.rotation(entity, camera)
 }
 
 #[derive(Default)]
 struct UpdateSystem {
     reader: EventReader<EditorEvent>,
+    snap: SnapState,
 }
 
 impl UpdateSystem {
@@ -786,32 +3586,127 @@ impl UpdateSystem {
     }
 }
 
+/// Identifies a transform, so that snapping starts over when a new one begins.
+type SnapSession = (
+    u32,
+    Option<TransformMode>,
+    Constraint,
+    TransformSpace,
+    Option<GizmoHandle>,
+);
+
+/// What the `EditorEvent`s of a transform add up to, and how much of it was applied.
+#[derive(Debug, Default)]
+struct SnapState {
+    session: Option<SnapSession>,
+    /// Translation of the entity when the transform started.
+    start: Vec3,
+    translation: Vec3,
+    applied_translation: Vec3,
+    /// The world-space rotations so far, composed.
+    rotation: Quat,
+    applied_rotation: Quat,
+    scale: Vec3,
+    applied_scale: Vec3,
+}
+
+impl SnapState {
+    fn reset(&mut self, session: SnapSession, start: Vec3) {
+        *self = Self {
+            session: Some(session),
+            start,
+            rotation: Quat::identity(),
+            applied_rotation: Quat::identity(),
+            scale: Vec3::one(),
+            applied_scale: Vec3::one(),
+            ..Default::default()
+        };
+    }
+}
+
+/// Whether transforms snap, which the snap key inverts while held.
+fn snapping(
+    settings: &EditorSettings,
+    keymap: &Keymap,
+    input: &Input<KeyCode>,
+    focus: &TextFocus,
+) -> bool {
+    settings.snap.enabled != keymap.pressed("snap", input, focus)
+}
+
+/// Applies `EditorEvent`s to the world; `transform_session_system` writes them to the
+/// scene once the transform is confirmed.
 fn update_system(
     mut state: Local<UpdateSystem>,
-    mut editor: ResMut<EditorCommands>,
+    input: Res<Input<KeyCode>>,
+    keymap: Res<Keymap>,
+    focus: Res<TextFocus>,
+    settings: Res<EditorSettings>,
+    mode: Res<EditorMode>,
+    gizmo: Res<GizmoState>,
     events: Res<Events<EditorEvent>>,
-    mut query: Query<(&Widget, &SelectablePickMesh, Mut<Transform>)>,
+    mut query: Query<(&Widget, &Selected, Mut<Transform>)>,
 ) {
+    let state = &mut *state;
+    let snapping = snapping(&settings, &keymap, &input, &focus);
     for (widget, select, mut transform) in &mut query.iter() {
-        if select.selected() {
-            for event in state.reader.iter(&events) {
-                match event {
-                    EditorEvent::Translate(value) => {
-                        transform.translate(*value);
-                        editor.insert_one(widget.0, transform.to_dynamic());
-                    }
-                    EditorEvent::Rotate(value) => {
-                        transform.rotate(*value);
-                        editor.insert_one(widget.0, transform.to_dynamic());
-                    }
-                    EditorEvent::Scale(value) => {
-                        transform.apply_non_uniform_scale(*value);
-                        editor.insert_one(widget.0, transform.to_dynamic());
+        if !select.0 {
+            continue;
+        }
+
+        let session = (
+            widget.0,
+            mode.transform,
+            mode.constraint,
+            mode.space,
+            gizmo.drag.map(|drag| drag.handle),
+        );
+        if state.snap.session != Some(session) {
+            state.snap.reset(session, transform.translation());
+        }
+        let snap = &mut state.snap;
+        for event in state.reader.iter(&events) {
+            match event {
+                EditorEvent::Translate(value) => snap.translation += *value,
+                EditorEvent::Rotate(value) => snap.rotation = (*value * snap.rotation).normalize(),
+                EditorEvent::Scale(value) => {
+                    if value.x() > 0.0 && value.y() > 0.0 && value.z() > 0.0 {
+                        snap.scale *= *value;
                     }
                 }
             }
-            break;
         }
+
+        let (translation, rotation, scale) = if snapping {
+            (
+                if mode.space == TransformSpace::Global {
+                    settings.snap.translation(snap.start, snap.translation)
+                } else {
+                    // the axes aren't aligned with the grid, so snap the distance instead
+                    settings.snap.distance(snap.translation)
+                },
+                settings.snap.rotation(snap.rotation),
+                settings.snap.scale(snap.scale),
+            )
+        } else {
+            (snap.translation, snap.rotation, snap.scale)
+        };
+
+        if translation != snap.applied_translation {
+            transform.translate(translation - snap.applied_translation);
+            snap.applied_translation = translation;
+        }
+        if rotation != snap.applied_rotation {
+            // the rotations are in world space, so they go before the entity's own
+            let delta = rotation * snap.applied_rotation.conjugate();
+            transform.set_rotation((delta * transform.rotation()).normalize());
+            snap.applied_rotation = rotation;
+        }
+        if scale != snap.applied_scale {
+            transform.apply_non_uniform_scale(scale / snap.applied_scale);
+            snap.applied_scale = scale;
+        }
+        break;
     }
 }
 
@@ -821,72 +3716,96 @@ fn apply_system(world: &mut World, resources: &mut Resources) {
 }
 
 fn save_system(
-    input: Res<Input<KeyCode>>,
-    editor: Res<Editor>,
+    mut editor: ResMut<Editor>,
     registry: Res<TypeRegistry>,
-    assets: Res<Assets<Scene>>,
+    mut assets: ResMut<Assets<Scene>>,
     default_bundles: Res<DefaultBundles>,
     default_properties: Res<DefaultProperties>,
+    mut watcher: ResMut<FileWatcher>,
+    mut palette: ResMut<CommandPalette>,
     mut query: Query<With<Button, (&ButtonFunction, Mutated<Interaction>)>>,
 ) {
-    if input.pressed(KeyCode::LControl) && input.just_pressed(KeyCode::S) {
-        editor
-            .write("assets/prefab.scn", &registry, &assets)
-            .unwrap();
-
-        let file = File::create("assets/editor_bundles.ron").unwrap();
-        let mut serializer = ::ron::Serializer::new(file, Some(Default::default()), false).unwrap();
-        let mut serializer = erased_serde::Serializer::erase(&mut serializer);
-        default_bundles
-            .to_dynamic()
-            .serializable(&registry.property.read())
-            .borrow()
-            .erased_serialize(&mut serializer)
-            .unwrap();
-
-        let file = File::create("assets/editor_properties.ron").unwrap();
-        let mut serializer = ::ron::Serializer::new(file, Some(Default::default()), false).unwrap();
-        let mut serializer = erased_serde::Serializer::erase(&mut serializer);
-        default_properties
-            .to_dynamic()
-            .serializable(&registry.property.read())
-            .borrow()
-            .erased_serialize(&mut serializer)
-            .unwrap();
+    if let Some(path) = palette.save_path.take() {
+        save(
+            &mut editor,
+            path,
+            &registry,
+            &mut assets,
+            &default_bundles,
+            &default_properties,
+            &mut watcher,
+        );
     }
     for (function, interaction) in &mut query.iter() {
-        match *interaction {
-            Interaction::Clicked => match function {
-                ButtonFunction::Save => {
-                    editor
-                        .write("assets/prefab.scn", &registry, &assets)
-                        .unwrap();
+        if let (ButtonFunction::Save, Interaction::Clicked) = (function, *interaction) {
+            let path = editor.path.clone();
+            save(
+                &mut editor,
+                path,
+                &registry,
+                &mut assets,
+                &default_bundles,
+                &default_properties,
+                &mut watcher,
+            );
+        }
+    }
+}
 
-                    let file = File::create("assets/editor_bundles.ron").unwrap();
-                    let mut serializer =
-                        ::ron::Serializer::new(file, Some(Default::default()), false).unwrap();
-                    let mut serializer = erased_serde::Serializer::erase(&mut serializer);
-                    default_bundles
-                        .to_dynamic()
-                        .serializable(&registry.property.read())
-                        .borrow()
-                        .erased_serialize(&mut serializer)
-                        .unwrap();
+/// Copies `scene`, since `Scene` itself isn't `Clone`.
+fn clone_scene(scene: &Scene) -> Scene {
+    Scene {
+        entities: scene
+            .entities
+            .iter()
+            .map(|scene_entity| scene::Entity {
+                entity: scene_entity.entity,
+                components: scene_entity
+                    .components
+                    .iter()
+                    .map(|component| component.clone_prop().as_properties().unwrap().to_dynamic())
+                    .collect(),
+            })
+            .collect(),
+    }
+}
 
-                    let file = File::create("assets/editor_properties.ron").unwrap();
-                    let mut serializer =
-                        ::ron::Serializer::new(file, Some(Default::default()), false).unwrap();
-                    let mut serializer = erased_serde::Serializer::erase(&mut serializer);
-                    default_properties
-                        .to_dynamic()
-                        .serializable(&registry.property.read())
-                        .borrow()
-                        .erased_serialize(&mut serializer)
-                        .unwrap();
-                }
-                _ => {}
-            },
-            _ => {}
-        }
+/// Writes the prefab to `path` and the bundle and property libraries.
+fn save(
+    editor: &mut Editor,
+    path: PathBuf,
+    registry: &TypeRegistry,
+    assets: &mut Assets<Scene>,
+    default_bundles: &DefaultBundles,
+    default_properties: &DefaultProperties,
+    watcher: &mut FileWatcher,
+) {
+    if let Err(err) = editor.write(&path, registry, assets) {
+        editor.notice = Some(format!("couldn't save {}: {}", path.display(), err));
+        return;
     }
+
+    let file = File::create(BUNDLES_PATH).unwrap();
+    let mut serializer = ::ron::Serializer::new(file, Some(Default::default()), false).unwrap();
+    let mut serializer = erased_serde::Serializer::erase(&mut serializer);
+    default_bundles
+        .to_dynamic()
+        .serializable(&registry.property.read())
+        .borrow()
+        .erased_serialize(&mut serializer)
+        .unwrap();
+
+    let file = File::create(PROPERTIES_PATH).unwrap();
+    let mut serializer = ::ron::Serializer::new(file, Some(Default::default()), false).unwrap();
+    let mut serializer = erased_serde::Serializer::erase(&mut serializer);
+    default_properties
+        .to_dynamic()
+        .serializable(&registry.property.read())
+        .borrow()
+        .erased_serialize(&mut serializer)
+        .unwrap();
+
+    watcher.touch(&editor.path);
+    watcher.touch(BUNDLES_PATH.as_ref());
+    watcher.touch(PROPERTIES_PATH.as_ref());
 }
@@ -0,0 +1,313 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use bevy::property::PropertyTypeRegistry;
+use bevy::scene::serde::SceneDeserializer;
+use hashbrown::HashSet;
+use serde::de::DeserializeSeed;
+
+use super::path::*;
+
+/// An asset referenced by an `Asset<T>` in a prefab.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    /// The prefab the asset is referenced from.
+    pub prefab: PathBuf,
+    /// The scene entity the asset is referenced from.
+    pub entity: u32,
+    /// `T` of the `Asset<T>`.
+    pub asset_type: String,
+    /// The path as written in the prefab.
+    pub path: String,
+    /// The path the asset will be loaded from.
+    pub resolved: PathBuf,
+}
+
+impl Dependency {
+    pub fn exists(&self) -> bool {
+        self.resolved.exists()
+    }
+
+    /// Whether the asset is a prefab itself.
+    pub fn is_prefab(&self) -> bool {
+        self.asset_type == "Scene"
+    }
+}
+
+/// The assets a set of prefabs pulls in.
+#[derive(Debug, Default, Clone)]
+pub struct Manifest {
+    pub prefabs: Vec<PathBuf>,
+    pub dependencies: Vec<Dependency>,
+}
+
+impl Manifest {
+    /// Builds the manifest of a prefab that's already loaded.
+    ///
+    /// Nested prefabs are listed, but not walked.
+    pub fn from_scene(scene: &Scene, prefab: &Path, root: &Path) -> Self {
+        Self {
+            prefabs: vec![prefab.to_path_buf()],
+            dependencies: scene_dependencies(scene, prefab, root),
+        }
+    }
+
+    /// Builds the manifest of the prefab at `prefab` and every prefab it references.
+    pub fn from_file(
+        prefab: &Path,
+        root: &Path,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<Self, ManifestError> {
+        let mut manifest = Self::default();
+        manifest.add_file(prefab, root, registry)?;
+        Ok(manifest)
+    }
+
+    /// Builds the manifest of every prefab in `root`.
+    pub fn from_dir(root: &Path, registry: &PropertyTypeRegistry) -> Result<Self, ManifestError> {
+        let mut manifest = Self::default();
+        let files = walk_dir(root).map_err(|err| ManifestError::Io(root.to_path_buf(), err))?;
+        for file in files {
+            if file
+                .extension()
+                .map_or(false, |extension| extension == "scn")
+            {
+                manifest.add_file(&file, root, registry)?;
+            }
+        }
+        Ok(manifest)
+    }
+
+    fn add_file(
+        &mut self,
+        prefab: &Path,
+        root: &Path,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<(), ManifestError> {
+        let prefab = normalize(prefab);
+        if self.prefabs.contains(&prefab) {
+            return Ok(());
+        }
+        let scene = read_scene(&prefab, registry)?;
+        let dependencies = scene_dependencies(&scene, &prefab, root);
+        self.prefabs.push(prefab);
+        for dependency in &dependencies {
+            if dependency.is_prefab() && dependency.exists() {
+                self.add_file(&dependency.resolved, root, registry)?;
+            }
+        }
+        self.dependencies.extend(dependencies);
+        Ok(())
+    }
+
+    /// Dependencies whose file doesn't exist.
+    pub fn missing(&self) -> impl Iterator<Item = &Dependency> {
+        self.dependencies
+            .iter()
+            .filter(|dependency| !dependency.exists())
+    }
+
+    /// Files in `root` that no prefab in the manifest references.
+    ///
+    /// Files with one of the `ignored` extensions, e.g. the prefabs themselves, are skipped.
+    pub fn unreferenced(&self, root: &Path, ignored: &[&str]) -> Result<Vec<PathBuf>, io::Error> {
+        let referenced = self
+            .dependencies
+            .iter()
+            .map(|dependency| normalize(&dependency.resolved))
+            .collect::<HashSet<_>>();
+        let files = walk_dir(root)?
+            .into_iter()
+            .filter(|file| {
+                !file.extension().map_or(false, |extension| {
+                    ignored.iter().any(|ignored| extension == *ignored)
+                })
+            })
+            .filter(|file| !referenced.contains(&normalize(file)))
+            .collect();
+        Ok(files)
+    }
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+    Io(PathBuf, io::Error),
+    Ron(PathBuf, ron::Error),
+}
+
+impl std::fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ManifestError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            ManifestError::Ron(path, err) => write!(f, "{}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+/// Lists the `Asset<T>`s of a prefab, including the ones nested in other components.
+pub fn scene_dependencies(scene: &Scene, prefab: &Path, root: &Path) -> Vec<Dependency> {
+    let origin = prefab_dir(prefab);
+    let mut dependencies = vec![];
+    for scene_entity in &scene.entities {
+        for component in &scene_entity.components {
+            for_each_asset_path(component, &mut |asset_type, path| {
+                if path.is_empty() {
+                    return;
+                }
+                dependencies.push(Dependency {
+                    prefab: prefab.to_path_buf(),
+                    entity: scene_entity.entity,
+                    asset_type: asset_type.rsplit("::").next().unwrap().to_string(),
+                    path: path.to_string(),
+                    resolved: resolve_asset_path(path, Some(origin), root),
+                });
+            });
+        }
+    }
+    dependencies
+}
+
+pub fn read_scene(path: &Path, registry: &PropertyTypeRegistry) -> Result<Scene, ManifestError> {
+    let bytes = fs::read(path).map_err(|err| ManifestError::Io(path.to_path_buf(), err))?;
+    let mut deserializer = ron::de::Deserializer::from_bytes(&bytes)
+        .map_err(|err| ManifestError::Ron(path.to_path_buf(), err))?;
+    SceneDeserializer {
+        property_type_registry: registry,
+    }
+    .deserialize(&mut deserializer)
+    .map_err(|err| ManifestError::Ron(path.to_path_buf(), err))
+}
+
+fn walk_dir(dir: &Path) -> Result<Vec<PathBuf>, io::Error> {
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(walk_dir(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Asset;
+    use bevy::property::DynamicProperties;
+    use bevy::scene;
+
+    fn scene_with(components: Vec<DynamicProperties>) -> Scene {
+        Scene {
+            entities: vec![scene::Entity {
+                entity: 7,
+                components,
+            }],
+        }
+    }
+
+    /// Creates an empty directory under the system temp dir for a test.
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("bevy_prefab_editor_manifest_{}", name));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn dependencies_resolve_against_the_prefab() {
+        let scene = scene_with(vec![
+            Asset::<Mesh>::new("./bed.gltf").to_dynamic(),
+            Asset::<Scene>::new("asset://rooms/bedroom.scn").to_dynamic(),
+            Asset::<Mesh>::new("").to_dynamic(),
+        ]);
+        let manifest = Manifest::from_scene(
+            &scene,
+            Path::new("assets/prefabs/house.scn"),
+            Path::new("assets"),
+        );
+        assert_eq!(
+            manifest.prefabs,
+            vec![PathBuf::from("assets/prefabs/house.scn")]
+        );
+        assert_eq!(manifest.dependencies.len(), 2);
+
+        let mesh = &manifest.dependencies[0];
+        assert_eq!(mesh.entity, 7);
+        assert_eq!(mesh.asset_type, "Mesh");
+        assert_eq!(mesh.path, "./bed.gltf");
+        assert_eq!(mesh.resolved, PathBuf::from("assets/prefabs/bed.gltf"));
+        assert!(!mesh.is_prefab());
+
+        let prefab = &manifest.dependencies[1];
+        assert_eq!(prefab.resolved, PathBuf::from("assets/rooms/bedroom.scn"));
+        assert!(prefab.is_prefab());
+    }
+
+    #[test]
+    fn missing_dependencies_are_reported() {
+        let root = temp_root("missing");
+        fs::write(root.join("bed.gltf"), "").unwrap();
+        let scene = scene_with(vec![
+            Asset::<Mesh>::new("./bed.gltf").to_dynamic(),
+            Asset::<Mesh>::new("./chair.gltf").to_dynamic(),
+        ]);
+        let manifest = Manifest::from_scene(&scene, &root.join("house.scn"), &root);
+        let missing = manifest
+            .missing()
+            .map(|dependency| dependency.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(missing, vec!["./chair.gltf"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn unreferenced_files_are_reported() {
+        let root = temp_root("unreferenced");
+        fs::create_dir_all(root.join("meshes")).unwrap();
+        fs::write(root.join("house.scn"), "").unwrap();
+        fs::write(root.join("meshes/bed.gltf"), "").unwrap();
+        fs::write(root.join("meshes/chair.gltf"), "").unwrap();
+        let scene = scene_with(vec![Asset::<Mesh>::new("../meshes/bed.gltf").to_dynamic()]);
+        let manifest = Manifest::from_scene(&scene, &root.join("prefabs/house.scn"), &root);
+        assert_eq!(manifest.missing().count(), 0);
+        assert_eq!(
+            manifest.unreferenced(&root, &["scn"]).unwrap(),
+            vec![root.join("meshes/chair.gltf")]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn clean_manifests_report_nothing() {
+        let root = temp_root("clean");
+        fs::write(root.join("bed.gltf"), "").unwrap();
+        let scene = scene_with(vec![Asset::<Mesh>::new("./bed.gltf").to_dynamic()]);
+        let manifest = Manifest::from_scene(&scene, &root.join("house.scn"), &root);
+        assert_eq!(manifest.missing().count(), 0);
+        assert!(manifest.unreferenced(&root, &["scn"]).unwrap().is_empty());
+
+        let registry = PropertyTypeRegistry::default();
+        let manifest = Manifest::from_dir(&root, &registry).unwrap();
+        assert!(manifest.prefabs.is_empty());
+        assert!(manifest.dependencies.is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn unreadable_prefabs_are_errors() {
+        let root = temp_root("unreadable");
+        let registry = PropertyTypeRegistry::default();
+        match Manifest::from_file(&root.join("house.scn"), &root, &registry) {
+            Err(ManifestError::Io(path, _)) => assert_eq!(path, root.join("house.scn")),
+            other => panic!("expected an io error, got {:?}", other),
+        }
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,151 @@
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use bevy::property::DynamicProperties;
+
+use super::path::{resolve_asset_path, AssetRoot, PrefabOrigin};
+use super::*;
+
+/// Serializable description of a `StandardMaterial`.
+///
+/// Resolved into a `Handle<StandardMaterial>` by `material_description_system` once the
+/// texture, if any, has loaded.  An empty texture path means an untextured material.
+#[derive(Debug, Clone, Properties)]
+pub struct MaterialDescription {
+    pub albedo: Color,
+    pub albedo_texture: Asset<Texture>,
+    pub shaded: bool,
+}
+
+impl MaterialDescription {
+    pub fn new(albedo: Color) -> Self {
+        Self {
+            albedo,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_texture<S: Into<String>>(mut self, path: S) -> Self {
+        self.albedo_texture = Asset::new(path);
+        self
+    }
+}
+
+impl Default for MaterialDescription {
+    fn default() -> Self {
+        Self {
+            albedo: Color::WHITE,
+            albedo_texture: Default::default(),
+            shaded: true,
+        }
+    }
+}
+
+/// Texture requested by a `MaterialDescription` that hasn't finished loading yet.
+#[derive(Debug, Clone, Copy)]
+pub struct MaterialTexture(Handle<Texture>);
+
+/// Turns `MaterialDescription` into a `Handle<StandardMaterial>`.
+///
+/// Like `into_asset_system`, a material created by this system is updated in place when the
+/// description changes, and freed once no entity uses it anymore.
+pub fn material_description_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    root: Res<AssetRoot>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut sources: ResMut<IntoAssetSources<MaterialDescription, StandardMaterial>>,
+    mut events: ResMut<Events<AssetLoadFailedEvent>>,
+    mut handles: Query<&Handle<StandardMaterial>>,
+    mut query: Query<(
+        Entity,
+        &MaterialDescription,
+        Option<&PrefabOrigin>,
+        Option<&MaterialTexture>,
+        Option<&Handle<StandardMaterial>>,
+    )>,
+) {
+    free_orphaned_assets(&mut materials, &mut sources, &mut handles);
+
+    for (e, description, origin, pending, handle) in &mut query.iter() {
+        let path = description.albedo_texture.path();
+        let texture = if path.is_empty() {
+            None
+        } else {
+            let texture = match pending {
+                Some(&MaterialTexture(texture)) => Ok(texture),
+                None => {
+                    let resolved = resolve_asset_path(
+                        path,
+                        origin.map(|origin| origin.0.as_path()),
+                        &root.0,
+                    );
+                    asset_server.load::<Texture, _>(&resolved)
+                }
+            };
+            match texture {
+                Ok(texture) => match asset_server.get_load_state(texture) {
+                    Some(LoadState::Loaded(_)) => Some(texture),
+                    Some(LoadState::Failed(_)) => {
+                        report_load_failed(
+                            &mut commands,
+                            &mut events,
+                            e,
+                            path.to_string(),
+                            format!("failed to load {}", path),
+                        );
+                        None
+                    }
+                    _ => {
+                        if pending.is_none() {
+                            commands.insert_one(e, MaterialTexture(texture));
+                        }
+                        continue;
+                    }
+                },
+                Err(err) => {
+                    report_load_failed(
+                        &mut commands,
+                        &mut events,
+                        e,
+                        path.to_string(),
+                        err.to_string(),
+                    );
+                    None
+                }
+            }
+        };
+
+        commands.remove_one::<MaterialDescription>(e);
+        if pending.is_some() {
+            commands.remove_one::<MaterialTexture>(e);
+        }
+
+        let material = StandardMaterial {
+            albedo: description.albedo,
+            albedo_texture: texture,
+            shaded: description.shaded,
+        };
+        match handle {
+            Some(&handle) if sources.get(&handle).is_some() => {
+                materials.set(handle, material);
+                sources.insert(handle, description.clone());
+            }
+            _ => {
+                let handle = materials.add(material);
+                sources.insert(handle, description.clone());
+                commands.insert_one(e, handle);
+            }
+        }
+    }
+}
+
+pub fn restore_material(
+    world: &World,
+    resources: &Resources,
+    entity: Entity,
+) -> Option<DynamicProperties> {
+    let handle = world.get::<Handle<StandardMaterial>>(entity).ok()?;
+    let sources = resources.get::<IntoAssetSources<MaterialDescription, StandardMaterial>>()?;
+    let description = sources.get(&handle)?;
+    Some(description.to_dynamic())
+}
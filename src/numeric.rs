@@ -0,0 +1,262 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Something wrong with a typed value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumericError {
+    Empty,
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    DivisionByZero,
+    /// A unit was given for a value that isn't an angle.
+    Unit(String),
+}
+
+impl std::fmt::Display for NumericError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            NumericError::Empty => write!(f, "no value"),
+            NumericError::UnexpectedChar(c) => write!(f, "unexpected {:?}", c),
+            NumericError::UnexpectedEnd => write!(f, "incomplete expression"),
+            NumericError::DivisionByZero => write!(f, "division by zero"),
+            NumericError::Unit(unit) => write!(f, "{} only applies to rotations", unit),
+        }
+    }
+}
+
+impl std::error::Error for NumericError {}
+
+/// A value typed while transforming.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumericValue {
+    pub value: f32,
+    /// Whether the value replaces the current one instead of being added to it.
+    pub absolute: bool,
+}
+
+/// Parses a typed value such as `2.5`, `-1/3`, `=4` or `(45 + 45)deg`.
+///
+/// A leading `=` makes the value absolute.  If `angle` is set the result is in radians,
+/// and the expression is in degrees unless it ends with `rad`; `deg` and `°` are also
+/// accepted.  Units are an error otherwise.
+pub fn parse_numeric(text: &str, angle: bool) -> Result<NumericValue, NumericError> {
+    let mut text = text.trim();
+    let absolute = text.starts_with('=');
+    if absolute {
+        text = text[1..].trim_start();
+    }
+
+    let mut radians = false;
+    for &(unit, is_radians) in &[("deg", false), ("°", false), ("rad", true)] {
+        if text.ends_with(unit) {
+            if !angle {
+                return Err(NumericError::Unit(unit.to_string()));
+            }
+            text = text[..text.len() - unit.len()].trim_end();
+            radians = is_radians;
+            break;
+        }
+    }
+
+    let value = evaluate(text)?;
+    let value = if angle && !radians {
+        value.to_radians()
+    } else {
+        value
+    };
+    Ok(NumericValue { value, absolute })
+}
+
+/// Evaluates an arithmetic expression with `+`, `-`, `*`, `/` and parentheses.
+pub fn evaluate(expression: &str) -> Result<f32, NumericError> {
+    let mut chars = expression.chars().peekable();
+    skip_whitespace(&mut chars);
+    if chars.peek().is_none() {
+        return Err(NumericError::Empty);
+    }
+    let value = sum(&mut chars)?;
+    match chars.next() {
+        Some(c) => Err(NumericError::UnexpectedChar(c)),
+        None => Ok(value),
+    }
+}
+
+type Input<'a> = Peekable<Chars<'a>>;
+
+fn skip_whitespace(chars: &mut Input) {
+    while chars.peek().map_or(false, |c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+fn sum(chars: &mut Input) -> Result<f32, NumericError> {
+    let mut value = product(chars)?;
+    loop {
+        match chars.peek() {
+            Some('+') => {
+                chars.next();
+                value += product(chars)?;
+            }
+            Some('-') => {
+                chars.next();
+                value -= product(chars)?;
+            }
+            _ => return Ok(value),
+        }
+    }
+}
+
+fn product(chars: &mut Input) -> Result<f32, NumericError> {
+    let mut value = unary(chars)?;
+    loop {
+        match chars.peek() {
+            Some('*') => {
+                chars.next();
+                value *= unary(chars)?;
+            }
+            Some('/') => {
+                chars.next();
+                let divisor = unary(chars)?;
+                if divisor == 0.0 {
+                    return Err(NumericError::DivisionByZero);
+                }
+                value /= divisor;
+            }
+            _ => return Ok(value),
+        }
+    }
+}
+
+fn unary(chars: &mut Input) -> Result<f32, NumericError> {
+    skip_whitespace(chars);
+    let value = match chars.peek() {
+        Some('-') => {
+            chars.next();
+            -unary(chars)?
+        }
+        Some('+') => {
+            chars.next();
+            unary(chars)?
+        }
+        Some('(') => {
+            chars.next();
+            let value = sum(chars)?;
+            match chars.next() {
+                Some(')') => value,
+                Some(c) => return Err(NumericError::UnexpectedChar(c)),
+                None => return Err(NumericError::UnexpectedEnd),
+            }
+        }
+        Some(_) => number(chars)?,
+        None => return Err(NumericError::UnexpectedEnd),
+    };
+    skip_whitespace(chars);
+    Ok(value)
+}
+
+fn number(chars: &mut Input) -> Result<f32, NumericError> {
+    let mut digits = String::new();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() || c == '.' {
+            digits.push(c);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+    if digits.is_empty() {
+        return Err(match chars.next() {
+            Some(c) => NumericError::UnexpectedChar(c),
+            None => NumericError::UnexpectedEnd,
+        });
+    }
+    // a lone "." is the start of a number being typed
+    if digits == "." {
+        return Ok(0.0);
+    }
+    digits
+        .parse()
+        .map_err(|_| NumericError::UnexpectedChar('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn value(text: &str) -> f32 {
+        parse_numeric(text, false).unwrap().value
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(value("1+2*3"), 7.0);
+        assert_eq!(value("(1 + 2) * 3"), 9.0);
+        assert_eq!(value("8 / 2 / 2"), 2.0);
+        assert_eq!(value("1 - 2 - 3"), -4.0);
+    }
+
+    #[test]
+    fn unary() {
+        assert_eq!(value("--2"), 2.0);
+        assert_eq!(value("-(1)"), -1.0);
+        assert_eq!(value("+3"), 3.0);
+        assert_eq!(value("2*-3"), -6.0);
+    }
+
+    #[test]
+    fn absolute() {
+        let parsed = parse_numeric("= 4", false).unwrap();
+        assert_eq!(parsed.value, 4.0);
+        assert!(parsed.absolute);
+        assert!(!parse_numeric("4", false).unwrap().absolute);
+    }
+
+    #[test]
+    fn angles_default_to_degrees() {
+        for text in &["90", "90deg", "90 °", "45 + 45deg"] {
+            let parsed = parse_numeric(text, true).unwrap();
+            assert!((parsed.value - FRAC_PI_2).abs() < 1e-6, "{}", text);
+        }
+        assert_eq!(parse_numeric("1.5rad", true).unwrap().value, 1.5);
+    }
+
+    #[test]
+    fn units_only_apply_to_angles() {
+        assert_eq!(
+            parse_numeric("90deg", false),
+            Err(NumericError::Unit("deg".to_string()))
+        );
+        assert_eq!(
+            parse_numeric("1rad", false),
+            Err(NumericError::Unit("rad".to_string()))
+        );
+    }
+
+    #[test]
+    fn division_by_zero() {
+        assert_eq!(parse_numeric("1/0", false), Err(NumericError::DivisionByZero));
+    }
+
+    #[test]
+    fn lone_decimal_point() {
+        assert_eq!(value("."), 0.0);
+        assert_eq!(value("-."), 0.0);
+        assert_eq!(value(".5"), 0.5);
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(
+            parse_numeric("1.2.3", false),
+            Err(NumericError::UnexpectedChar('.'))
+        );
+        assert_eq!(parse_numeric("", false), Err(NumericError::Empty));
+        assert_eq!(parse_numeric("=", false), Err(NumericError::Empty));
+        assert_eq!(parse_numeric("(1", false), Err(NumericError::UnexpectedEnd));
+        assert_eq!(parse_numeric("1 +", false), Err(NumericError::UnexpectedEnd));
+        assert_eq!(parse_numeric("1)", false), Err(NumericError::UnexpectedChar(')')));
+        assert_eq!(parse_numeric("1x", false), Err(NumericError::UnexpectedChar('x')));
+    }
+}
@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+use bevy::property::{DynamicProperties, Properties};
+
+/// A line of the outliner, one per scene entity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlinerRow {
+    /// The `scene::Entity::entity` of the entity.
+    pub entity: u32,
+    pub depth: usize,
+    /// The `EntityName` of the entity, or its id if it has none.
+    pub name: String,
+    /// Short type names of the entity's components.
+    pub components: Vec<String>,
+}
+
+/// Lists the entities of a scene, with children following their parent.
+///
+/// Entities whose `Parent` isn't in the scene are listed as roots.
+pub fn outline(scene: &Scene) -> Vec<OutlinerRow> {
+    let parents = scene
+        .entities
+        .iter()
+        .map(|scene_entity| {
+            scene_parent(&scene_entity.components).filter(|&parent| {
+                parent != scene_entity.entity
+                    && scene.entities.iter().any(|other| other.entity == parent)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut rows = vec![];
+    let mut visited = vec![false; scene.entities.len()];
+    for (index, parent) in parents.iter().enumerate() {
+        if parent.is_none() {
+            outline_entity(scene, &parents, index, 0, &mut visited, &mut rows);
+        }
+    }
+    // entities in a parent cycle are never reached from a root
+    for index in 0..scene.entities.len() {
+        if !visited[index] {
+            outline_entity(scene, &parents, index, 0, &mut visited, &mut rows);
+        }
+    }
+    rows
+}
+
+fn outline_entity(
+    scene: &Scene,
+    parents: &[Option<u32>],
+    index: usize,
+    depth: usize,
+    visited: &mut Vec<bool>,
+    rows: &mut Vec<OutlinerRow>,
+) {
+    if visited[index] {
+        return;
+    }
+    visited[index] = true;
+
+    let scene_entity = &scene.entities[index];
+    rows.push(OutlinerRow {
+        entity: scene_entity.entity,
+        depth,
+        name: entity_name(&scene_entity.components)
+            .unwrap_or_else(|| format!("Entity {}", scene_entity.entity)),
+        components: scene_entity
+            .components
+            .iter()
+            .map(|component| short_name(&component.type_name))
+            .collect(),
+    });
+
+    for (child, parent) in parents.iter().enumerate() {
+        if *parent == Some(scene_entity.entity) {
+            outline_entity(scene, parents, child, depth + 1, visited, rows);
+        }
+    }
+}
+
+fn is_component(component: &DynamicProperties, name: &str) -> bool {
+    component.type_name == name || component.type_name.ends_with(&format!("::{}", name))
+}
+
+/// Returns the scene id of the entity's `Parent`, if any.
+pub fn scene_parent(components: &[DynamicProperties]) -> Option<u32> {
+    components
+        .iter()
+        .find(|component| is_component(component, "Parent"))
+        .and_then(|parent| parent.prop_with_index(0))
+        .and_then(|entity| entity.any().downcast_ref::<Entity>())
+        .map(|entity| entity.id())
+}
+
+/// Returns `root` followed by the scene ids of its descendants.
+pub fn scene_subtree(scene: &Scene, root: u32) -> Vec<u32> {
+    let mut subtree = vec![root];
+    let mut index = 0;
+    while index < subtree.len() {
+        for scene_entity in &scene.entities {
+            if scene_parent(&scene_entity.components) == Some(subtree[index])
+                && !subtree.contains(&scene_entity.entity)
+            {
+                subtree.push(scene_entity.entity);
+            }
+        }
+        index += 1;
+    }
+    subtree
+}
+
+/// Points the entity's `Parent` at the scene entity `parent`, if it has a `Parent`.
+pub fn set_scene_parent(components: &mut [DynamicProperties], parent: u32) {
+    if let Some(entity) = components
+        .iter_mut()
+        .find(|component| is_component(component, "Parent"))
+        .and_then(|component| component.prop_with_index_mut(0))
+    {
+        entity.set(&Entity::new(parent));
+    }
+}
+
+/// Returns the `EntityName` of the entity, if any.
+pub fn entity_name(components: &[DynamicProperties]) -> Option<String> {
+    components
+        .iter()
+        .find(|component| is_component(component, "EntityName"))
+        .and_then(|name| name.prop_with_index(0))
+        .and_then(|name| name.any().downcast_ref::<String>())
+        .filter(|name| !name.is_empty())
+        .cloned()
+}
+
+/// Removes the module paths from a type name, e.g. `Asset<Mesh>`.
+pub fn short_name(type_name: &str) -> String {
+    let mut short = String::new();
+    let mut segment = String::new();
+    for c in type_name.chars() {
+        match c {
+            '<' | '>' | ',' | ' ' | '(' | ')' | '[' | ']' | ';' | '&' => {
+                short.push_str(segment.rsplit("::").next().unwrap());
+                segment.clear();
+                short.push(c);
+            }
+            c => segment.push(c),
+        }
+    }
+    short.push_str(segment.rsplit("::").next().unwrap());
+    short
+}
@@ -0,0 +1,251 @@
+use std::path::{Component, Path, PathBuf};
+
+use bevy::property::{DynamicProperties, Properties};
+
+/// Prefix of asset paths resolved against the `AssetRoot` instead of the prefab.
+pub const ASSET_ROOT_PREFIX: &str = "asset://";
+
+/// Directory of the prefab an entity was spawned from.
+///
+/// `Asset<T>` paths starting with `./` or `../` are resolved against it.
+#[derive(Debug, Clone)]
+pub struct PrefabOrigin(pub PathBuf);
+
+impl PrefabOrigin {
+    /// Creates the origin of the prefab file at `path`.
+    pub fn of<P: AsRef<Path>>(path: P) -> Self {
+        Self(prefab_dir(path.as_ref()).to_path_buf())
+    }
+}
+
+/// Root of the asset folder.
+///
+/// Used for `asset://` paths, and for prefab-relative paths of entities that don't have a
+/// `PrefabOrigin`.
+#[derive(Debug, Clone)]
+pub struct AssetRoot(pub PathBuf);
+
+impl Default for AssetRoot {
+    fn default() -> Self {
+        Self("assets".into())
+    }
+}
+
+pub fn prefab_dir(path: &Path) -> &Path {
+    path.parent().unwrap_or_else(|| Path::new(""))
+}
+
+pub fn is_prefab_relative(path: &str) -> bool {
+    path.starts_with("./") || path.starts_with("../")
+}
+
+/// Resolves an `Asset<T>` path to a path the `AssetServer` can load.
+///
+/// Any other path is resolved against the working directory, as before.
+pub fn resolve_asset_path(path: &str, origin: Option<&Path>, root: &Path) -> PathBuf {
+    if path.starts_with(ASSET_ROOT_PREFIX) {
+        normalize(&root.join(&path[ASSET_ROOT_PREFIX.len()..]))
+    } else if is_prefab_relative(path) {
+        normalize(&origin.unwrap_or(root).join(path))
+    } else {
+        PathBuf::from(path)
+    }
+}
+
+/// Rewrites a prefab-relative path so it points at the same file from `to` instead of `from`.
+pub fn relocate_asset_path(path: &str, from: &Path, to: &Path) -> String {
+    if !is_prefab_relative(path) {
+        return path.to_string();
+    }
+    let target = normalize(&from.join(path));
+    let relative = relative_path(&target, &normalize(to));
+    let relative = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("/");
+    if relative.starts_with("../") {
+        relative
+    } else {
+        format!("./{}", relative)
+    }
+}
+
+/// Lexically removes `.` and `..` components, without touching the file system.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                _ => normalized.push(".."),
+            },
+            component => normalized.push(component.as_os_str()),
+        }
+    }
+    normalized
+}
+
+fn relative_path(target: &Path, base: &Path) -> PathBuf {
+    let target = target.components().collect::<Vec<_>>();
+    let base = base.components().collect::<Vec<_>>();
+    let common = target
+        .iter()
+        .zip(&base)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &target[common..] {
+        relative.push(component.as_os_str());
+    }
+    relative
+}
+
+/// Returns `T` for the type name of an `Asset<T>` component.
+pub fn asset_type_name(type_name: &str) -> Option<&str> {
+    let start = type_name.find('<')?;
+    let outer = &type_name[..start];
+    if (outer == "Asset" || outer.ends_with("::Asset")) && type_name.ends_with('>') {
+        Some(&type_name[start + 1..type_name.len() - 1])
+    } else {
+        None
+    }
+}
+
+/// Like `for_each_asset_path`, but lets `f` rewrite the paths in place, e.g. to relocate
+/// them when the prefab moves.
+pub fn for_each_asset_path_mut<F: FnMut(&str, &mut String)>(
+    component: &mut DynamicProperties,
+    f: &mut F,
+) {
+    if let Some(asset_type) = asset_type_name(&component.type_name) {
+        let asset_type = asset_type.to_string();
+        if let Some(path) = component
+            .prop_mut("path")
+            .and_then(|path| path.any_mut().downcast_mut::<String>())
+        {
+            f(&asset_type, path);
+        }
+        return;
+    }
+    for prop in &mut component.props {
+        if let Some(nested) = prop.any_mut().downcast_mut::<DynamicProperties>() {
+            for_each_asset_path_mut(nested, f);
+        }
+    }
+}
+
+/// Calls `f` with the asset type and path of every `Asset<T>` in `component`, including the
+/// ones nested in other components.
+pub fn for_each_asset_path<F: FnMut(&str, &str)>(component: &DynamicProperties, f: &mut F) {
+    if let Some(asset_type) = asset_type_name(&component.type_name) {
+        if let Some(path) = component
+            .prop("path")
+            .and_then(|path| path.any().downcast_ref::<String>())
+        {
+            f(asset_type, path);
+        }
+        return;
+    }
+    for prop in &component.props {
+        if let Some(nested) = prop.any().downcast_ref::<DynamicProperties>() {
+            for_each_asset_path(nested, f);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_removes_dots() {
+        assert_eq!(normalize(Path::new("a/./b/../c")), PathBuf::from("a/c"));
+        assert_eq!(normalize(Path::new("../a/../../b")), PathBuf::from("../../b"));
+        assert_eq!(normalize(Path::new("./")), PathBuf::new());
+    }
+
+    #[test]
+    fn prefab_relative_paths_resolve_against_the_origin() {
+        let root = Path::new("assets");
+        let origin = Path::new("assets/prefabs");
+        assert_eq!(
+            resolve_asset_path("./bed.gltf", Some(origin), root),
+            PathBuf::from("assets/prefabs/bed.gltf")
+        );
+        assert_eq!(
+            resolve_asset_path("../meshes/bed.gltf", Some(origin), root),
+            PathBuf::from("assets/meshes/bed.gltf")
+        );
+    }
+
+    #[test]
+    fn prefab_relative_paths_fall_back_to_the_root() {
+        assert_eq!(
+            resolve_asset_path("./bed.gltf", None, Path::new("assets")),
+            PathBuf::from("assets/bed.gltf")
+        );
+    }
+
+    #[test]
+    fn root_paths_resolve_against_the_root() {
+        assert_eq!(
+            resolve_asset_path(
+                "asset://meshes/bed.gltf",
+                Some(Path::new("assets/prefabs")),
+                Path::new("assets")
+            ),
+            PathBuf::from("assets/meshes/bed.gltf")
+        );
+    }
+
+    #[test]
+    fn other_paths_are_left_alone() {
+        assert_eq!(
+            resolve_asset_path("assets/bed.gltf", Some(Path::new("prefabs")), Path::new("root")),
+            PathBuf::from("assets/bed.gltf")
+        );
+        assert_eq!(
+            relocate_asset_path("assets/bed.gltf", Path::new("a"), Path::new("b")),
+            "assets/bed.gltf"
+        );
+    }
+
+    #[test]
+    fn relocated_paths_point_at_the_same_file() {
+        let from = Path::new("assets/prefabs");
+        assert_eq!(
+            relocate_asset_path("./bed.gltf", from, Path::new("assets/prefabs/rooms")),
+            "../bed.gltf"
+        );
+        assert_eq!(
+            relocate_asset_path("../meshes/bed.gltf", from, Path::new("assets/meshes")),
+            "./bed.gltf"
+        );
+        assert_eq!(
+            relocate_asset_path("./bed.gltf", from, Path::new("other")),
+            "../assets/prefabs/bed.gltf"
+        );
+        assert_eq!(
+            relocate_asset_path("./bed.gltf", from, Path::new("assets/./prefabs")),
+            "./bed.gltf"
+        );
+    }
+
+    #[test]
+    fn asset_type_names() {
+        assert_eq!(asset_type_name("Asset<Mesh>"), Some("Mesh"));
+        assert_eq!(
+            asset_type_name("bevy_prefab_editor::Asset<bevy_render::mesh::mesh::Mesh>"),
+            Some("bevy_render::mesh::mesh::Mesh")
+        );
+        assert_eq!(asset_type_name("IntoAsset<Color, StandardMaterial>"), None);
+        assert_eq!(asset_type_name("Transform"), None);
+    }
+}
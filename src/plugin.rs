@@ -0,0 +1,173 @@
+use bevy::ecs::IntoThreadLocalSystem;
+use bevy::prelude::*;
+use bevy::property::DeserializeProperty;
+
+use super::entity::DefaultProperties;
+use super::material::*;
+use super::outliner::short_name;
+use super::path::AssetRoot;
+use super::*;
+
+type Registration = Box<dyn Fn(&mut AppBuilder) + Send + Sync + 'static>;
+
+/// Registers prefab components along with the systems that resolve them and their
+/// `DefaultProperties` entries.
+///
+/// ```ignore
+/// app.add_plugin(
+///     PrefabPlugin::new()
+///         .asset::<Mesh>()
+///         .into_asset::<Color, StandardMaterial>()
+///         .material()
+///         .default_component::<GlobalTransform>(),
+/// );
+/// ```
+#[derive(Default)]
+pub struct PrefabPlugin {
+    registrations: Vec<Registration>,
+}
+
+impl PrefabPlugin {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn register<F: Fn(&mut AppBuilder) + Send + Sync + 'static>(mut self, f: F) -> Self {
+        self.registrations.push(Box::new(f));
+        self
+    }
+
+    /// Registers `Asset<T>`, which is loaded into a `Handle<T>`.
+    pub fn asset<T: Send + Sync + 'static>(self) -> Self {
+        self.register(|app| {
+            app.register_component::<Asset<T>>()
+                .init_resource::<AssetPlaceholder<T>>()
+                .init_resource::<AssetSources<T>>()
+                .add_system_to_stage(stage::LAST, load_asset_system::<T>.system());
+            let resources = app.resources_mut();
+            resources
+                .get_mut::<AssetRestorers>()
+                .unwrap()
+                .add(restore_asset::<T>);
+            resources
+                .get_mut::<DefaultProperties>()
+                .unwrap()
+                .insert_default::<Asset<T>>();
+        })
+    }
+
+    /// Registers `IntoAsset<T, U>`, which is converted into a `Handle<U>`.
+    pub fn into_asset<T, U>(self) -> Self
+    where
+        T: Property + DeserializeProperty + Default + Clone + Send + Sync + 'static,
+        U: From<T> + Send + Sync + 'static,
+    {
+        self.register(|app| {
+            app.register_component::<IntoAsset<T, U>>()
+                .init_resource::<IntoAssetSources<T, U>>()
+                .add_system_to_stage(stage::LAST, into_asset_system::<T, U>.system());
+            let resources = app.resources_mut();
+            resources
+                .get_mut::<AssetRestorers>()
+                .unwrap()
+                .add(restore_into_asset::<T, U>);
+            resources
+                .get_mut::<DefaultProperties>()
+                .unwrap()
+                .insert_default::<IntoAsset<T, U>>();
+        })
+    }
+
+    /// Registers `DefaultComponent<T>`, which is replaced by `T::default()`.
+    pub fn default_component<T: Default + Send + Sync + 'static>(self) -> Self {
+        self.register(|app| {
+            app.register_component::<DefaultComponent<T>>()
+                .add_system_to_stage(stage::LAST, default_component_system::<T>.system());
+            app.resources_mut()
+                .get_mut::<DefaultProperties>()
+                .unwrap()
+                .insert_default::<DefaultComponent<T>>();
+        })
+    }
+
+    /// Registers `MaterialDescription`, which is resolved into a `Handle<StandardMaterial>`.
+    pub fn material(self) -> Self {
+        self.register(|app| {
+            app.register_component::<MaterialDescription>()
+                .init_resource::<IntoAssetSources<MaterialDescription, StandardMaterial>>()
+                .add_system_to_stage(stage::LAST, material_description_system.system());
+            let resources = app.resources_mut();
+            resources
+                .get_mut::<AssetRestorers>()
+                .unwrap()
+                .add(restore_material);
+            resources
+                .get_mut::<DefaultProperties>()
+                .unwrap()
+                .insert_default::<MaterialDescription>();
+        })
+    }
+
+    /// Registers `ResourceComponent<T>`, which is replaced by `T::from_resources`.
+    pub fn resource_component<T: FromResources + Send + Sync + 'static>(self) -> Self {
+        self.register(|app| {
+            app.register_component::<ResourceComponent<T>>()
+                .add_system_to_stage(
+                    stage::LAST,
+                    resource_component_system::<T>.thread_local_system(),
+                );
+            app.resources_mut()
+                .get_mut::<DefaultProperties>()
+                .unwrap()
+                .insert_default::<ResourceComponent<T>>();
+        })
+    }
+
+    /// Registers `ConstructedComponent<Args, T>`, which is replaced by
+    /// `T::from((args, resources))`.
+    pub fn constructed_component<Args, T>(self) -> Self
+    where
+        Args: Property + DeserializeProperty + Default + Clone + Send + Sync + 'static,
+        T: for<'a> From<(Args, &'a Resources)> + Send + Sync + 'static,
+    {
+        self.register(|app| {
+            app.register_component::<ConstructedComponent<Args, T>>()
+                .add_system_to_stage(
+                    stage::LAST,
+                    constructed_component_system::<Args, T>.thread_local_system(),
+                );
+            app.resources_mut()
+                .get_mut::<DefaultProperties>()
+                .unwrap()
+                .insert_default::<ConstructedComponent<Args, T>>();
+        })
+    }
+}
+
+impl Plugin for PrefabPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if app.resources().get::<AssetRoot>().is_none() {
+            app.init_resource::<AssetRoot>();
+        }
+        if app.resources().get::<AssetRestorers>().is_none() {
+            app.init_resource::<AssetRestorers>();
+        }
+        if app.resources().get::<DefaultProperties>().is_none() {
+            app.init_resource::<DefaultProperties>();
+        }
+        app.add_event::<AssetLoadFailedEvent>()
+            .register_component::<EntityName>();
+        app.resources_mut()
+            .get_mut::<DefaultProperties>()
+            .unwrap()
+            .insert_default::<EntityName>();
+        for registration in &self.registrations {
+            registration(app);
+        }
+    }
+}
+
+/// `std::any::type_name` without the module paths, e.g. `Asset<Mesh>`.
+pub fn short_type_name<T: ?Sized>() -> String {
+    short_name(std::any::type_name::<T>())
+}
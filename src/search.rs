@@ -0,0 +1,90 @@
+/// Scores how well `pattern` matches `candidate`, ignoring case.
+///
+/// Every character of `pattern` has to appear in `candidate`, in order.  Consecutive
+/// characters, characters at the start of a word and earlier matches score higher.
+pub fn fuzzy_score(pattern: &str, candidate: &str) -> Option<i32> {
+    let candidate = candidate.chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous = None;
+    for p in pattern.chars().filter(|c| !c.is_whitespace()) {
+        let p = p.to_lowercase().collect::<String>();
+        let index = (next..candidate.len())
+            .find(|&i| candidate[i].to_lowercase().collect::<String>() == p)?;
+
+        score += 1;
+        if previous.map_or(false, |previous| previous + 1 == index) {
+            score += 5;
+        }
+        if index == 0 || is_word_start(&candidate, index) {
+            score += 3;
+        }
+        score -= (index - next) as i32;
+
+        previous = Some(index);
+        next = index + 1;
+    }
+    Some(score)
+}
+
+fn is_word_start(candidate: &[char], index: usize) -> bool {
+    let (previous, current) = (candidate[index - 1], candidate[index]);
+    !previous.is_alphanumeric() || (previous.is_lowercase() && current.is_uppercase())
+}
+
+/// Returns the candidates matching `pattern`, best match first.
+///
+/// An empty pattern matches everything, sorted by name.
+pub fn fuzzy_search<'a, I: IntoIterator<Item = &'a str>>(
+    pattern: &str,
+    candidates: I,
+) -> Vec<&'a str> {
+    let mut matches = candidates
+        .into_iter()
+        .filter_map(|candidate| fuzzy_score(pattern, candidate).map(|score| (score, candidate)))
+        .collect::<Vec<_>>();
+    matches.sort_by(|(a_score, a), (b_score, b)| b_score.cmp(a_score).then_with(|| a.cmp(b)));
+    matches
+        .into_iter()
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_match_in_order_ignoring_case() {
+        assert!(fuzzy_score("trfm", "Transform").is_some());
+        assert!(fuzzy_score("TRANS", "transform").is_some());
+        assert!(fuzzy_score("global transform", "GlobalTransform").is_some());
+        assert_eq!(fuzzy_score("mt", "Transform"), None);
+        assert_eq!(fuzzy_score("xyz", "Transform"), None);
+    }
+
+    #[test]
+    fn word_starts_and_runs_score_higher() {
+        assert!(fuzzy_score("tr", "Transform") > fuzzy_score("tr", "GlobalTransform"));
+        assert!(fuzzy_score("gt", "GlobalTransform") > fuzzy_score("gt", "Light"));
+        assert!(fuzzy_score("mesh", "Mesh") > fuzzy_score("mesh", "MainPassHandle"));
+    }
+
+    #[test]
+    fn search_ranks_best_match_first() {
+        let candidates = vec!["GlobalTransform", "Light", "Transform"];
+        assert_eq!(
+            fuzzy_search("tr", candidates.clone()),
+            vec!["Transform", "GlobalTransform"]
+        );
+        assert_eq!(fuzzy_search("xyz", candidates.clone()), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn empty_patterns_match_everything_by_name() {
+        assert_eq!(
+            fuzzy_search("", vec!["Transform", "Light", "Draw"]),
+            vec!["Draw", "Light", "Transform"]
+        );
+    }
+}
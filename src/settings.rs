@@ -0,0 +1,226 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(PathBuf, io::Error),
+    Ron(PathBuf, ron::Error),
+}
+
+impl std::fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SettingsError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            SettingsError::Ron(path, err) => write!(f, "{}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+/// Editor preferences, read from `assets/editor_settings.ron`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditorSettings {
+    #[serde(default)]
+    pub snap: SnapSettings,
+}
+
+impl EditorSettings {
+    pub fn from_str(text: &str, path: &Path) -> Result<Self, SettingsError> {
+        ron::de::from_str(text).map_err(|err| SettingsError::Ron(path.to_path_buf(), err))
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, SettingsError> {
+        let text =
+            fs::read_to_string(path).map_err(|err| SettingsError::Io(path.to_path_buf(), err))?;
+        Self::from_str(&text, path)
+    }
+}
+
+/// Steps that transforms snap to.
+///
+/// Snapping is applied to the whole transform since it started, not to each mouse motion,
+/// so small motions still add up to a step.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapSettings {
+    /// Whether snapping is on while the snap key isn't held; holding it inverts this.
+    pub enabled: bool,
+    /// Size of the translation grid, in world units.
+    pub translate: f32,
+    /// Rotation step, in degrees.
+    pub rotate: f32,
+    /// Scale step, as a fraction of the original size.
+    pub scale: f32,
+}
+
+impl Default for SnapSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            translate: 0.5,
+            rotate: 15.0,
+            scale: 0.1,
+        }
+    }
+}
+
+impl SnapSettings {
+    /// Snaps the translation `offset` from `start` so the moved axes land on the grid.
+    ///
+    /// Axes the offset doesn't move along are left alone.
+    pub fn translation(&self, start: Vec3, offset: Vec3) -> Vec3 {
+        let snap_axis = |start: f32, offset: f32| {
+            if offset == 0.0 {
+                0.0
+            } else {
+                snap(start + offset, self.translate) - start
+            }
+        };
+        Vec3::new(
+            snap_axis(start.x(), offset.x()),
+            snap_axis(start.y(), offset.y()),
+            snap_axis(start.z(), offset.z()),
+        )
+    }
+
+    /// Snaps the length of a translation `offset` to the grid size, keeping its direction.
+    ///
+    /// Used when moving along axes that aren't aligned with the grid.
+    pub fn distance(&self, offset: Vec3) -> Vec3 {
+        let length = offset.length();
+        if length == 0.0 {
+            offset
+        } else {
+            offset * (snap(length, self.translate) / length)
+        }
+    }
+
+    /// Snaps the angle of `rotation` to the rotation step, keeping its axis.
+    pub fn rotation(&self, rotation: Quat) -> Quat {
+        let (axis, angle) = axis_angle(rotation);
+        Quat::from_axis_angle(axis, snap(angle, self.rotate.to_radians()))
+    }
+
+    /// Snaps scale factors, one per axis, to multiples of the scale step.
+    pub fn scale(&self, factors: Vec3) -> Vec3 {
+        let snap_axis = |factor: f32| {
+            let snapped = 1.0 + snap(factor - 1.0, self.scale);
+            if snapped <= 0.0 {
+                self.scale.abs()
+            } else {
+                snapped
+            }
+        };
+        Vec3::new(
+            snap_axis(factors.x()),
+            snap_axis(factors.y()),
+            snap_axis(factors.z()),
+        )
+    }
+}
+
+/// Rounds `value` to the nearest multiple of `step`, or leaves it alone if `step` isn't
+/// positive.
+pub fn snap(value: f32, step: f32) -> f32 {
+    if step <= 0.0 {
+        value
+    } else {
+        (value / step).round() * step
+    }
+}
+
+/// Splits a rotation into an axis and an angle in radians.
+pub fn axis_angle(rotation: Quat) -> (Vec3, f32) {
+    let rotation = rotation.normalize();
+    let w = rotation.w().max(-1.0).min(1.0);
+    let angle = 2.0 * w.acos();
+    let sin = (1.0 - w * w).sqrt();
+    if sin < 1e-6 {
+        (Vec3::unit_x(), 0.0)
+    } else {
+        let axis = Vec3::new(rotation.x(), rotation.y(), rotation.z()) / sin;
+        (axis, angle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn settings() -> SnapSettings {
+        SnapSettings {
+            enabled: true,
+            translate: 0.5,
+            rotate: 15.0,
+            scale: 0.1,
+        }
+    }
+
+    fn assert_near(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn snap_rounds_to_the_nearest_step() {
+        assert_eq!(snap(0.7, 0.5), 0.5);
+        assert_eq!(snap(0.8, 0.5), 1.0);
+        assert_eq!(snap(-0.8, 0.5), -1.0);
+        assert_eq!(snap(0.7, 0.0), 0.7);
+        assert_eq!(snap(0.7, -1.0), 0.7);
+    }
+
+    #[test]
+    fn translation_lands_moved_axes_on_the_grid() {
+        let snapped = settings().translation(Vec3::new(0.1, 0.2, 0.3), Vec3::new(0.6, 0.0, -0.1));
+        assert_near(snapped, Vec3::new(0.4, 0.0, -0.3));
+    }
+
+    #[test]
+    fn translation_leaves_small_offsets_at_the_start_cell() {
+        let snapped = settings().translation(Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.2, 0.0, 0.0));
+        assert_near(snapped, Vec3::zero());
+    }
+
+    #[test]
+    fn distance_keeps_the_direction() {
+        let snapped = settings().distance(Vec3::new(0.6, 0.8, 0.0) * 1.2);
+        assert_near(snapped, Vec3::new(0.6, 0.8, 0.0));
+        assert_near(settings().distance(Vec3::zero()), Vec3::zero());
+    }
+
+    #[test]
+    fn rotation_snaps_the_angle_around_its_axis() {
+        let rotation = Quat::from_axis_angle(Vec3::unit_y(), 20f32.to_radians());
+        let (axis, angle) = axis_angle(settings().rotation(rotation));
+        assert_near(axis, Vec3::unit_y());
+        assert!((angle - 15f32.to_radians()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rotation_snaps_small_angles_to_none() {
+        let rotation = Quat::from_axis_angle(Vec3::unit_x(), 5f32.to_radians());
+        let (_, angle) = axis_angle(settings().rotation(rotation));
+        assert!(angle.abs() < 1e-4);
+    }
+
+    #[test]
+    fn scale_snaps_each_factor_and_stays_positive() {
+        let snapped = settings().scale(Vec3::new(1.26, 0.94, 0.01));
+        assert_near(snapped, Vec3::new(1.3, 0.9, 0.1));
+    }
+
+    #[test]
+    fn axis_angle_splits_rotations() {
+        let (axis, angle) = axis_angle(Quat::from_axis_angle(Vec3::unit_z(), FRAC_PI_2));
+        assert_near(axis, Vec3::unit_z());
+        assert!((angle - FRAC_PI_2).abs() < 1e-5);
+        assert_eq!(axis_angle(Quat::identity()), (Vec3::unit_x(), 0.0));
+    }
+}
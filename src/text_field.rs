@@ -0,0 +1,170 @@
+/// Whether a text field has keyboard focus, so that shortcuts should be ignored.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TextFocus(pub bool);
+
+/// Editable text with a caret and an optional selection.
+///
+/// Positions are counted in chars, not bytes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TextField {
+    text: String,
+    caret: usize,
+    /// The other end of the selection, if any.
+    anchor: Option<usize>,
+    /// Set when editing was cancelled with Escape, so the edit should be discarded.
+    pub cancelled: bool,
+}
+
+impl TextField {
+    pub fn new<S: Into<String>>(text: S) -> Self {
+        let mut field = Self::default();
+        field.set(text);
+        field
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text, selecting all of it so that typing overwrites it.
+    pub fn set<S: Into<String>>(&mut self, text: S) {
+        self.text = text.into();
+        self.caret = self.len();
+        self.anchor = Some(0).filter(|_| self.caret > 0);
+        self.cancelled = false;
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    fn byte_index(&self, index: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(index)
+            .map_or(self.text.len(), |(byte, _)| byte)
+    }
+
+    /// The selected range, start first.
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        let anchor = self.anchor?;
+        if anchor == self.caret {
+            None
+        } else {
+            Some((anchor.min(self.caret), anchor.max(self.caret)))
+        }
+    }
+
+    pub fn selected_text(&self) -> Option<&str> {
+        let (start, end) = self.selection()?;
+        Some(&self.text[self.byte_index(start)..self.byte_index(end)])
+    }
+
+    /// Removes the selected text, returning whether there was any.
+    fn delete_selection(&mut self) -> bool {
+        let (start, end) = match self.selection() {
+            Some(selection) => selection,
+            None => {
+                self.anchor = None;
+                return false;
+            }
+        };
+        let range = self.byte_index(start)..self.byte_index(end);
+        self.text.replace_range(range, "");
+        self.caret = start;
+        self.anchor = None;
+        true
+    }
+
+    /// Inserts `text` at the caret, replacing the selection.
+    pub fn insert(&mut self, text: &str) {
+        self.delete_selection();
+        let byte = self.byte_index(self.caret);
+        self.text.insert_str(byte, text);
+        self.caret += text.chars().count();
+    }
+
+    pub fn backspace(&mut self) {
+        if !self.delete_selection() && self.caret > 0 {
+            self.caret -= 1;
+            let byte = self.byte_index(self.caret);
+            self.text.remove(byte);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if !self.delete_selection() && self.caret < self.len() {
+            let byte = self.byte_index(self.caret);
+            self.text.remove(byte);
+        }
+    }
+
+    /// Moves the caret to `index`, extending the selection if `select` is set.
+    pub fn move_to(&mut self, index: usize, select: bool) {
+        if select {
+            self.anchor = self.anchor.or(Some(self.caret));
+        } else {
+            self.anchor = None;
+        }
+        self.caret = index.min(self.len());
+    }
+
+    pub fn left(&mut self, select: bool) {
+        match self.selection() {
+            Some((start, _)) if !select => self.move_to(start, false),
+            _ => self.move_to(self.caret.saturating_sub(1), select),
+        }
+    }
+
+    pub fn right(&mut self, select: bool) {
+        match self.selection() {
+            Some((_, end)) if !select => self.move_to(end, false),
+            _ => self.move_to(self.caret + 1, select),
+        }
+    }
+
+    pub fn home(&mut self, select: bool) {
+        self.move_to(0, select);
+    }
+
+    pub fn end(&mut self, select: bool) {
+        self.move_to(self.len(), select);
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.caret = self.len();
+    }
+
+    /// The text with a `|` at the caret and the selection in brackets.
+    pub fn display(&self) -> String {
+        let mut display = String::new();
+        let selection = self.selection();
+        for (index, c) in self.text.chars().enumerate() {
+            self.display_marks(index, selection, &mut display);
+            display.push(c);
+        }
+        self.display_marks(self.len(), selection, &mut display);
+        display
+    }
+
+    fn display_marks(&self, index: usize, selection: Option<(usize, usize)>, display: &mut String) {
+        if let Some((start, end)) = selection {
+            if index == end && self.caret != end {
+                display.push(']');
+            }
+            if index == start && self.caret == start {
+                display.push('|');
+            }
+            if index == start {
+                display.push('[');
+            }
+            if index == end && self.caret == end {
+                display.push(']');
+                display.push('|');
+            }
+        } else if index == self.caret {
+            display.push('|');
+        }
+    }
+}